@@ -0,0 +1,93 @@
+use criterion::*;
+use fluidex_common::types::{BigInt, FrExt};
+use fluidex_common::Fr;
+use rand::prelude::*;
+
+fn generate_input() -> Vec<u32> {
+    (0..1000).map(|_| thread_rng().gen()).collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let data = generate_input();
+    let frs: Vec<Fr> = data.iter().map(|x| Fr::from_u32(*x)).collect();
+
+    c.bench_function("test_fr_from_u32_limbs", |b| {
+        b.iter(|| data.iter().map(|x| Fr::from_u32(*x)).for_each(drop))
+    });
+    c.bench_function("test_fr_from_u32_origin", |b| {
+        b.iter(|| data.iter().map(|x| old_impl::from_u32(*x)).for_each(drop))
+    });
+    c.bench_function("test_fr_to_u32_limbs", |b| {
+        b.iter(|| frs.iter().map(|x| x.to_u32()).for_each(drop))
+    });
+    c.bench_function("test_fr_to_u32_origin", |b| {
+        b.iter(|| frs.iter().map(old_impl::to_u32).for_each(drop))
+    });
+    c.bench_function("test_fr_to_i64_limbs", |b| {
+        b.iter(|| frs.iter().map(|x| x.to_i64()).for_each(drop))
+    });
+    c.bench_function("test_fr_to_i64_origin", |b| {
+        b.iter(|| frs.iter().map(old_impl::to_i64).for_each(drop))
+    });
+    c.bench_function("test_fr_to_bigint_limbs", |b| {
+        b.iter(|| frs.iter().map(|x| x.to_bigint()).for_each(drop))
+    });
+    c.bench_function("test_fr_to_bigint_origin", |b| {
+        b.iter(|| frs.iter().map(old_impl::to_bigint).for_each(drop))
+    });
+    let bigints: Vec<BigInt> = frs.iter().map(|x| x.to_bigint()).collect();
+    c.bench_function("test_fr_from_bigint_limbs", |b| {
+        b.iter_batched(
+            || bigints.clone(),
+            |bigints| bigints.into_iter().map(Fr::from_bigint).for_each(drop),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("test_fr_from_bigint_origin", |b| {
+        b.iter_batched(
+            || bigints.clone(),
+            |bigints| {
+                bigints
+                    .into_iter()
+                    .map(old_impl::from_bigint)
+                    .for_each(drop)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
+
+/// The string based conversions used before operating on `FrRepr` limbs directly.
+mod old_impl {
+    use fluidex_common::ff::{from_hex, to_hex, PrimeField};
+    use fluidex_common::types::BigInt;
+    use fluidex_common::Fr;
+
+    pub fn from_u32(x: u32) -> Fr {
+        PrimeField::from_str(&format!("{}", x)).unwrap()
+    }
+
+    pub fn from_bigint(x: BigInt) -> Fr {
+        let mut s = x.to_str_radix(16);
+        if s.len() % 2 != 0 {
+            // convert "f" to "0f"
+            s.insert(0, '0');
+        }
+        from_hex(&s).unwrap()
+    }
+
+    pub fn to_bigint(x: &Fr) -> BigInt {
+        BigInt::parse_bytes(to_hex(x).as_bytes(), 16).unwrap()
+    }
+
+    pub fn to_u32(x: &Fr) -> u32 {
+        to_bigint(x).to_str_radix(10).parse::<u32>().unwrap()
+    }
+
+    pub fn to_i64(x: &Fr) -> i64 {
+        to_bigint(x).to_str_radix(10).parse::<i64>().unwrap()
+    }
+}
@@ -0,0 +1,43 @@
+use criterion::*;
+use fluidex_common::babyjubjub_rs::{Point, PrivateKey};
+use fluidex_common::l2::account::{L2Account, SignatureBJJ};
+use fluidex_common::types::FrExt;
+use fluidex_common::Fr;
+use rand::prelude::*;
+
+fn generate_input() -> Vec<(Fr, SignatureBJJ, Point)> {
+    (0..1000)
+        .map(|_| {
+            let key = PrivateKey::import(thread_rng().gen::<[u8; 32]>().to_vec()).unwrap();
+            let hash = Fr::from_u64(thread_rng().gen());
+            (hash, key.sign(hash.to_bigint()).unwrap(), key.public())
+        })
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let data = generate_input();
+
+    c.bench_function("test_verify_sequential", |b| {
+        b.iter(|| {
+            data.iter()
+                .map(|(hash, sig, pubkey)| {
+                    L2Account::verify_raw_using_pubkey(*hash, sig.clone(), pubkey.clone())
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    c.bench_function("test_verify_batch", |b| {
+        b.iter(|| L2Account::verify_batch(&data))
+    });
+    c.bench_function("test_verify_batch_all", |b| {
+        b.iter(|| L2Account::verify_batch_all(&data))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
@@ -1,4 +1,5 @@
 use crate::db::TimestampDbType;
+use crate::l2::pubdata::{check_block, decode_block, L2Tx, PubdataError};
 use serde::Serialize;
 
 #[derive(sqlx::Type, Serialize, Debug, Clone)]
@@ -19,3 +20,75 @@ pub struct L2Block {
     pub detail: serde_json::Value,
     pub created_time: TimestampDbType,
 }
+
+impl L2Block {
+    /// Decode `raw_public_data` into transactions, `None` if it is not stored.
+    pub fn public_data_txs(&self) -> Option<Result<Vec<L2Tx>, PubdataError>> {
+        self.raw_public_data.as_deref().map(decode_block)
+    }
+
+    /// Check `raw_public_data` against the transactions listed in `detail["txs"]`.
+    pub fn check_public_data(&self) -> Result<(), PubdataError> {
+        let data = self
+            .raw_public_data
+            .as_deref()
+            .ok_or(PubdataError::Missing("raw_public_data"))?;
+        let txs = self
+            .detail
+            .get("txs")
+            .ok_or(PubdataError::Missing("detail.txs"))?;
+        let txs: Vec<L2Tx> = serde_json::from_value(txs.clone())?;
+        check_block(data, &txs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::l2::pubdata::encode_block;
+    use crate::types::Float40;
+
+    #[test]
+    fn test_check_public_data() {
+        let txs = vec![L2Tx::Transfer {
+            from: 1,
+            to: 2,
+            token_id: 0,
+            amount: Float40 {
+                exponent: 18,
+                significand: 3,
+            },
+        }];
+        let mut block = L2Block {
+            block_id: 1,
+            new_root: "0x00".to_string(),
+            raw_public_data: Some(encode_block(&txs).unwrap()),
+            status: BlockStatus::Uncommited,
+            l1_tx_hash: None,
+            detail: serde_json::json!({ "txs": txs }),
+            created_time: chrono::Utc::now().naive_utc(),
+        };
+        block.check_public_data().unwrap();
+
+        block.detail = serde_json::json!({ "txs": [] });
+        assert!(matches!(
+            block.check_public_data(),
+            Err(PubdataError::Mismatch(0))
+        ));
+        block.detail = serde_json::json!({ "txs": [{ "type": "unknown" }] });
+        assert!(matches!(
+            block.check_public_data(),
+            Err(PubdataError::Detail(_))
+        ));
+        block.detail = serde_json::json!({});
+        assert!(matches!(
+            block.check_public_data(),
+            Err(PubdataError::Missing("detail.txs"))
+        ));
+        block.raw_public_data = None;
+        assert!(matches!(
+            block.check_public_data(),
+            Err(PubdataError::Missing("raw_public_data"))
+        ));
+    }
+}
@@ -1,8 +1,7 @@
 use crate::ff::Field;
 use crate::serde::FrStr;
-use crate::types::FrExt;
+use crate::types::{FrExt, FrExtError, PubkeyExt};
 use crate::Fr;
-use anyhow::Result;
 use babyjubjub_rs::{self, Point, PrivateKey};
 use ethers::core::k256::ecdsa::digest::generic_array::GenericArray;
 use ethers::core::k256::ecdsa::digest::{BlockInput, Digest, FixedOutput, Output, Reset, Update};
@@ -16,19 +15,39 @@ use ethers::prelude::Signature as EthersSignature;
 use ethers::signers::to_eip155_v;
 use ethers::utils::hash_message;
 use once_cell::sync::Lazy;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 pub type SignatureBJJ = babyjubjub_rs::Signature;
 
-static CHAIN_ID: Lazy<u32> = Lazy::new(|| {
+#[derive(Debug, thiserror::Error)]
+pub enum L2AccountError {
+    #[error(transparent)]
+    HexDecode(#[from] hex::FromHexError),
+    #[error("invalid secp256k1 private key")]
+    InvalidSigningKey(#[source] ethers::core::k256::ecdsa::Error),
+    #[error("babyjubjub key import failed: {0}")]
+    Import(String),
+    #[error("babyjubjub signing failed: {0}")]
+    Sign(String),
+    #[error("signature component out of the field")]
+    InvalidSignature(#[source] FrExtError),
+}
+
+type Result<T, E = L2AccountError> = std::result::Result<T, E>;
+
+/// Default chain ID for both layers, read from the `CHAIN_ID` env var, 1 if unset.
+static CHAIN_ID: Lazy<u64> = Lazy::new(|| {
     std::env::var("CHAIN_ID")
         .unwrap_or_else(|_| "1".to_string())
-        .parse::<u32>()
+        .parse::<u64>()
         .unwrap_or(1)
 });
-static CREATE_L2_ACCOUNT_MSG: Lazy<String> =
-    Lazy::new(|| format!("FLUIDEX_L2_ACCOUNT\nChain ID: {}.", *CHAIN_ID));
+
+/// Default account creation message, `{chain_id}` is replaced by the L2 chain ID.
+pub const DEFAULT_MESSAGE_TEMPLATE: &str = "FLUIDEX_L2_ACCOUNT\nChain ID: {chain_id}.";
 
 pub struct L2Account {
     priv_key: PrivateKey,
@@ -49,31 +68,74 @@ impl fmt::Debug for L2Account {
     }
 }
 
-impl L2Account {
-    pub fn from_private_key_string(private_key: &str) -> Result<Self, String> {
-        let private_key_bytes = hex::decode(private_key.trim_start_matches("0x")).unwrap();
-        let private_key = SigningKey::from_bytes(&private_key_bytes).unwrap();
+/// Derive [`L2Account`]s from L1 keys with explicit chain IDs and creation message.
+///
+/// The defaults use the `CHAIN_ID` env var for both chain IDs and
+/// [`DEFAULT_MESSAGE_TEMPLATE`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2AccountBuilder {
+    l1_chain_id: u64,
+    l2_chain_id: u64,
+    message_template: String,
+}
+
+impl Default for L2AccountBuilder {
+    fn default() -> Self {
+        Self {
+            l1_chain_id: *CHAIN_ID,
+            l2_chain_id: *CHAIN_ID,
+            message_template: DEFAULT_MESSAGE_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl L2AccountBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain ID of the EIP-155 signature over the creation message.
+    pub fn l1_chain_id(mut self, chain_id: u64) -> Self {
+        self.l1_chain_id = chain_id;
+        self
+    }
+
+    /// Chain ID written into the creation message, which the derived key depends on.
+    pub fn l2_chain_id(mut self, chain_id: u64) -> Self {
+        self.l2_chain_id = chain_id;
+        self
+    }
+
+    /// Creation message template, `{chain_id}` is replaced by the L2 chain ID.
+    pub fn message_template<S: Into<String>>(mut self, template: S) -> Self {
+        self.message_template = template.into();
+        self
+    }
 
-        Self::from_private_key(&private_key)
+    /// The account creation message signed by the L1 key.
+    pub fn message(&self) -> String {
+        self.message_template
+            .replace("{chain_id}", &self.l2_chain_id.to_string())
     }
 
-    pub fn from_private_key(private_key: &SigningKey) -> Result<Self, String> {
-        let signature = sign_msg_with_signing_key(private_key, &*CREATE_L2_ACCOUNT_MSG);
+    pub fn from_private_key_string(&self, private_key: &str) -> Result<L2Account> {
+        let private_key_bytes = hex::decode(private_key.trim_start_matches("0x"))?;
+        let private_key = SigningKey::from_bytes(&private_key_bytes)
+            .map_err(L2AccountError::InvalidSigningKey)?;
+
+        self.from_private_key(&private_key)
+    }
+
+    pub fn from_private_key(&self, private_key: &SigningKey) -> Result<L2Account> {
+        let signature = sign_msg_with_signing_key(private_key, &self.message(), self.l1_chain_id);
         let seed = &signature.to_vec()[0..32];
 
-        let priv_key = PrivateKey::import(seed.to_vec())?;
+        let priv_key = PrivateKey::import(seed.to_vec()).map_err(L2AccountError::Import)?;
         let pub_key: Point = priv_key.public();
-        let ax = pub_key.x;
-        let ay = pub_key.y;
-        let bjj_compressed = pub_key.compress();
-        let sign = if bjj_compressed[31] & 0x80 != 0x00 {
-            Fr::one()
-        } else {
-            Fr::zero()
-        };
-        let bjj_pub_key = hex::encode(bjj_compressed);
+        let (ax, ay, sign) = pub_key.to_circuit_components();
+        let bjj_pub_key = hex::encode(pub_key.compress());
 
-        Ok(Self {
+        Ok(L2Account {
             priv_key,
             pub_key,
             ax,
@@ -82,24 +144,31 @@ impl L2Account {
             bjj_pub_key,
         })
     }
+}
 
-    pub fn sign_hash(&self, hash: Fr) -> Result<Signature, String> {
-        let sig = self.sign_hash_raw(hash)?;
-        let s = Fr::from_bigint(sig.s);
-        Ok(Signature {
-            hash,
-            s,
-            r8x: sig.r_b8.x,
-            r8y: sig.r_b8.y,
-        })
+impl L2Account {
+    /// Derive with the default [`L2AccountBuilder`].
+    pub fn from_private_key_string(private_key: &str) -> Result<Self> {
+        L2AccountBuilder::default().from_private_key_string(private_key)
     }
 
-    pub fn sign_hash_packed(&self, hash: Fr) -> Result<[u8; 64], String> {
+    /// Derive with the default [`L2AccountBuilder`].
+    pub fn from_private_key(private_key: &SigningKey) -> Result<Self> {
+        L2AccountBuilder::default().from_private_key(private_key)
+    }
+
+    pub fn sign_hash(&self, hash: Fr) -> Result<Signature> {
+        Signature::from_raw(hash, &self.sign_hash_raw(hash)?)
+    }
+
+    pub fn sign_hash_packed(&self, hash: Fr) -> Result<[u8; 64]> {
         Ok(self.sign_hash_raw(hash)?.compress())
     }
 
-    pub fn sign_hash_raw(&self, hash: Fr) -> Result<SignatureBJJ, String> {
-        self.priv_key.sign(hash.to_bigint())
+    pub fn sign_hash_raw(&self, hash: Fr) -> Result<SignatureBJJ> {
+        self.priv_key
+            .sign(hash.to_bigint())
+            .map_err(L2AccountError::Sign)
     }
 
     pub fn verify(&self, sig: Signature) -> bool {
@@ -122,6 +191,36 @@ impl L2Account {
         };
         Self::verify_raw_using_pubkey(sig.hash, sig_bjj, pub_key.clone())
     }
+
+    fn verify_item((hash, sig_bjj, pub_key): &(Fr, SignatureBJJ, Point)) -> bool {
+        Self::verify_raw_using_pubkey(*hash, sig_bjj.clone(), pub_key.clone())
+    }
+
+    /// Verify each `(hash, signature, pubkey)` across threads.
+    #[cfg(feature = "parallel")]
+    pub fn verify_batch(items: &[(Fr, SignatureBJJ, Point)]) -> Vec<bool> {
+        items.par_iter().map(Self::verify_item).collect()
+    }
+
+    /// Verify each `(hash, signature, pubkey)`, across threads with the `parallel` feature.
+    #[cfg(not(feature = "parallel"))]
+    pub fn verify_batch(items: &[(Fr, SignatureBJJ, Point)]) -> Vec<bool> {
+        items.iter().map(Self::verify_item).collect()
+    }
+
+    /// Like [`L2Account::verify_batch`], only telling whether all signatures are valid and
+    /// stopping at the first invalid one.
+    #[cfg(feature = "parallel")]
+    pub fn verify_batch_all(items: &[(Fr, SignatureBJJ, Point)]) -> bool {
+        items.par_iter().all(Self::verify_item)
+    }
+
+    /// Like [`L2Account::verify_batch`], only telling whether all signatures are valid and
+    /// stopping at the first invalid one.
+    #[cfg(not(feature = "parallel"))]
+    pub fn verify_batch_all(items: &[(Fr, SignatureBJJ, Point)]) -> bool {
+        items.iter().all(Self::verify_item)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -137,13 +236,14 @@ pub struct Signature {
 }
 
 impl Signature {
-    pub fn from_raw(hash: Fr, sig: &SignatureBJJ) -> Self {
-        Self {
+    /// Fails if `sig.s` is negative or not below the field modulus.
+    pub fn from_raw(hash: Fr, sig: &SignatureBJJ) -> Result<Self> {
+        Ok(Self {
             hash,
-            s: Fr::from_bigint(sig.clone().s),
-            r8x: sig.clone().r_b8.x,
-            r8y: sig.clone().r_b8.y,
-        }
+            s: Fr::try_from_bigint(sig.s.clone()).map_err(L2AccountError::InvalidSignature)?,
+            r8x: sig.r_b8.x,
+            r8y: sig.r_b8.y,
+        })
     }
 }
 
@@ -160,13 +260,12 @@ impl Default for Signature {
 
 /// Signs the message with the signing key and returns the ethers core signature.
 /// Copied from https://github.com/gakonst/ethers-rs/blob/01cc80769c291fc80f5b1e9173b7b580ae6b6413/ethers-signers/src/wallet/mod.rs#L71
-fn sign_msg_with_signing_key(priv_key: &SigningKey, msg: &str) -> EthersSignature {
+fn sign_msg_with_signing_key(priv_key: &SigningKey, msg: &str, chain_id: u64) -> EthersSignature {
     let msg_hash = hash_message(msg);
     let digest = Sha256Proxy::from(msg_hash);
     let recoverable_sig: RecoverableSignature = priv_key.sign_digest(digest);
 
-    //TODO: what if we want to use different CHAIN_ID for layer 1, but keep using 1 for layer 2?
-    let v = to_eip155_v(recoverable_sig.recovery_id(), *CHAIN_ID as u64);
+    let v = to_eip155_v(recoverable_sig.recovery_id(), chain_id);
 
     let r_bytes: FieldBytes<Secp256k1> = recoverable_sig.r().into();
     let s_bytes: FieldBytes<Secp256k1> = recoverable_sig.s().into();
@@ -261,6 +360,7 @@ impl<D: Digest> FixedOutput for ProxyDigest<D> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::BigInt;
 
     #[test]
     fn test_l2_account_sign_and_verify() {
@@ -308,6 +408,21 @@ mod tests {
             "e6949e09d2f4165df14bc6ded7e21d03bc3235edffd7eeb93d1548ea967c36062a34a6534a2c3a98b007e623a5e60b49c0bc9fd9ec6f9c50e273b0b0abcd5903"
         );
 
+        let raw = account.sign_hash_raw(hash).unwrap();
+        assert_eq!(Signature::from_raw(hash, &raw).unwrap(), signature);
+        let mut forged = raw.clone();
+        forged.s = BigInt::parse_bytes(
+            b"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            10,
+        )
+        .unwrap();
+        assert!(matches!(
+            Signature::from_raw(hash, &forged),
+            Err(L2AccountError::InvalidSignature(_))
+        ));
+        forged.s = BigInt::from(-1);
+        assert!(Signature::from_raw(hash, &forged).is_err());
+
         // Verify the signature.
         assert!(account.verify(signature));
         let unpacked_signature = babyjubjub_rs::decompress_signature(&packed_signature).unwrap();
@@ -317,4 +432,84 @@ mod tests {
             account.pub_key
         ));
     }
+
+    #[test]
+    fn test_l2_account_builder() {
+        let private_key = "0b22f852cd07386bce533f2038821fdcebd9c5ced9e3cd51e3a05d421dbfd785";
+        let builder = L2AccountBuilder::new().l1_chain_id(1).l2_chain_id(1);
+        assert_eq!(builder.message(), "FLUIDEX_L2_ACCOUNT\nChain ID: 1.");
+        let account = builder.from_private_key_string(private_key).unwrap();
+        assert_eq!(
+            account.bjj_pub_key,
+            "7b70843a42114e88149e3961495c03f9a41292c8b97bd1e2026597d185478293"
+        );
+
+        // the L1 chain ID only changes `v` of the creation signature, not the derived key
+        let l1_testnet = L2AccountBuilder::new().l1_chain_id(5).l2_chain_id(1);
+        assert_eq!(
+            l1_testnet
+                .from_private_key_string(private_key)
+                .unwrap()
+                .bjj_pub_key,
+            account.bjj_pub_key
+        );
+
+        let l2_testnet = L2AccountBuilder::new().l1_chain_id(5).l2_chain_id(5);
+        assert_eq!(l2_testnet.message(), "FLUIDEX_L2_ACCOUNT\nChain ID: 5.");
+        assert_ne!(
+            l2_testnet
+                .from_private_key_string(private_key)
+                .unwrap()
+                .bjj_pub_key,
+            account.bjj_pub_key
+        );
+
+        let custom = L2AccountBuilder::new()
+            .l2_chain_id(7)
+            .message_template("MY_L2 {chain_id}");
+        assert_eq!(custom.message(), "MY_L2 7");
+    }
+
+    #[test]
+    fn test_l2_account_invalid_keys() {
+        assert!(matches!(
+            L2Account::from_private_key_string("0xzz"),
+            Err(L2AccountError::HexDecode(_))
+        ));
+        assert!(matches!(
+            L2Account::from_private_key_string(&"00".repeat(32)),
+            Err(L2AccountError::InvalidSigningKey(_))
+        ));
+        assert!(matches!(
+            L2Account::from_private_key_string("0b22"),
+            Err(L2AccountError::InvalidSigningKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_l2_account_verify_batch() {
+        let account = L2Account::from_private_key_string(
+            "0b22f852cd07386bce533f2038821fdcebd9c5ced9e3cd51e3a05d421dbfd785",
+        )
+        .unwrap();
+        let mut items: Vec<_> = (0..8u32)
+            .map(|i| {
+                let hash = Fr::from_u32(i);
+                (
+                    hash,
+                    account.sign_hash_raw(hash).unwrap(),
+                    account.pub_key.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(L2Account::verify_batch(&items), vec![true; 8]);
+        assert!(L2Account::verify_batch_all(&items));
+
+        items[3].0 = Fr::from_u32(100);
+        let results = L2Account::verify_batch(&items);
+        assert!(!results[3]);
+        assert_eq!(results.iter().filter(|valid| **valid).count(), 7);
+        assert!(!L2Account::verify_batch_all(&items));
+        assert!(L2Account::verify_batch_all(&[]));
+    }
 }
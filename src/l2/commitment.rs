@@ -0,0 +1,141 @@
+//! The block commitment submitted to L1 and used as the public input of the block proof.
+//!
+//! The commitment is `sha256(old_root || new_root || block_id || public_data) mod r`, where
+//! the roots are 32-byte big-endian field elements, `block_id` is a 32-byte big-endian
+//! `uint256` as `abi.encodePacked` lays it out, and `r` is the scalar field modulus.
+//!
+//! `block_id` is an `i64` like `L2Block::block_id`, and must not be negative.
+//!
+//! On L1 this is
+//! `uint256(sha256(abi.encodePacked(oldRoot, newRoot, blockId, publicData))) % r` with
+//! `bytes32` roots, a `uint256` block id and `bytes` public data.
+// TODO: the contract source is not vendored here, so the test vectors are computed
+// independently; add the commitment of a known block as submitted on L1.
+use sha2::{Digest, Sha256};
+
+use crate::types::FrExt;
+use crate::Fr;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CommitmentError {
+    #[error("negative block id {0}")]
+    NegativeBlockId(i64),
+}
+
+type Result<T, E = CommitmentError> = std::result::Result<T, E>;
+
+/// A block commitment with the bytes it is computed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCommitment {
+    /// The sha256 input.
+    pub preimage: Vec<u8>,
+    /// The raw sha256 digest.
+    pub digest: [u8; 32],
+    /// The digest reduced into the field.
+    pub commitment: Fr,
+}
+
+impl BlockCommitment {
+    pub fn new(old_root: &Fr, new_root: &Fr, block_id: i64, public_data: &[u8]) -> Result<Self> {
+        if block_id < 0 {
+            return Err(CommitmentError::NegativeBlockId(block_id));
+        }
+        let mut preimage = Vec::with_capacity(96 + public_data.len());
+        preimage.extend_from_slice(&old_root.to_be_bytes());
+        preimage.extend_from_slice(&new_root.to_be_bytes());
+        preimage.extend_from_slice(&[0u8; 24]);
+        preimage.extend_from_slice(&block_id.to_be_bytes());
+        preimage.extend_from_slice(public_data);
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&Sha256::digest(&preimage));
+        let commitment = Fr::from_be_bytes_reduced(digest);
+        Ok(Self {
+            preimage,
+            digest,
+            commitment,
+        })
+    }
+}
+
+/// Shorthand for [`BlockCommitment::new`], returning only the field element.
+pub fn block_commitment(
+    old_root: &Fr,
+    new_root: &Fr,
+    block_id: i64,
+    public_data: &[u8],
+) -> Result<Fr> {
+    Ok(BlockCommitment::new(old_root, new_root, block_id, public_data)?.commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::BigInt;
+
+    #[test]
+    fn test_block_commitment() {
+        let old_root = Fr::from_u32(1);
+        let new_root = Fr::from_u32(2);
+        let c = BlockCommitment::new(&old_root, &new_root, 3, &[0xab, 0xcd]).unwrap();
+        assert_eq!(c.preimage.len(), 98);
+        assert_eq!(c.preimage[31], 1);
+        assert_eq!(c.preimage[63], 2);
+        assert_eq!(c.preimage[95], 3);
+        assert_eq!(&c.preimage[96..], &[0xab, 0xcd]);
+        assert_eq!(c.digest.to_vec(), Sha256::digest(&c.preimage).to_vec());
+
+        let modulus = BigInt::parse_bytes(
+            b"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            10,
+        )
+        .unwrap();
+        let digest = BigInt::from_bytes_be(num_bigint::Sign::Plus, &c.digest);
+        assert_eq!(c.commitment.to_bigint(), digest % modulus);
+        assert_eq!(
+            block_commitment(&old_root, &new_root, 3, &[0xab, 0xcd]).unwrap(),
+            c.commitment
+        );
+        assert_ne!(
+            block_commitment(&old_root, &new_root, 4, &[0xab, 0xcd]).unwrap(),
+            c.commitment
+        );
+        assert!(matches!(
+            BlockCommitment::new(&old_root, &new_root, -1, &[]),
+            Err(CommitmentError::NegativeBlockId(-1))
+        ));
+    }
+
+    // Known answers of the expression in the module docs, computed with Python's hashlib
+    // rather than taken from the contract.
+    #[test]
+    fn test_block_commitment_vectors() {
+        let c = BlockCommitment::new(&Fr::from_u32(1), &Fr::from_u32(2), 3, &[0xab, 0xcd]).unwrap();
+        assert_eq!(
+            hex::encode(c.digest),
+            "2efd90f38f3da428d766b9cfe16084e43e7622fe9fce69735e2a1ce4d02d989a"
+        );
+        assert_eq!(
+            c.commitment.to_decimal_string(),
+            "21254403760045178506656124165369697543085232194826829113290997992200900286618"
+        );
+
+        // the digest is beyond the modulus, so this one is reduced
+        let public_data: Vec<u8> = (0..20).collect();
+        let c = BlockCommitment::new(
+            &Fr::from_u32(0x2a),
+            &Fr::from_u64(0x1234567890abcdef),
+            i64::MAX,
+            &public_data,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(c.digest),
+            "bbab4a588d615203f71f68572c90a22533dd20572bd437958b2d12144384fdc9"
+        );
+        assert_eq!(
+            c.commitment.to_decimal_string(),
+            "19220418034224130294999684335379757986056832808802654681103255387383013899718"
+        );
+    }
+}
@@ -1,2 +1,5 @@
 #[cfg(feature = "l2-account")]
 pub mod account;
+#[cfg(feature = "sha2")]
+pub mod commitment;
+pub mod pubdata;
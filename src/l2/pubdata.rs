@@ -0,0 +1,447 @@
+//! Public data of rollup blocks, as stored in `L2Block::raw_public_data` and submitted to L1.
+//!
+//! Every transaction starts with a [`TX_TYPE_BITS`] type tag followed by its fields, most
+//! significant bit first, and is padded to a whole byte. Amounts are [`Float40`]. A block
+//! is the concatenation of its transactions, so zero padding decodes as [`L2Tx::Nop`].
+//!
+//! This layout is defined by this module, it is not derived from a circuit spec. Services
+//! writing `raw_public_data` and services reading it must both go through [`encode_block`]
+//! and [`decode_block`], and any change here breaks blocks already stored. The widths
+//! follow the types of this crate: account ids hold the non-negative range of
+//! `AccountDesc::id`, token ids are 16 bits, and amounts use the [`Float40`] encoding,
+//! decoded strictly, see [`Floats::from_encoded_bigint`](crate::types::Floats::from_encoded_bigint).
+//!
+//! The fields of each transaction, in order after the tag:
+//!
+//! - [`L2Tx::Deposit`], [`L2Tx::Withdraw`], [`L2Tx::FullExit`]: account id, token id, amount
+//! - [`L2Tx::Transfer`]: from, to, token id, amount
+//! - [`L2Tx::SpotTrade`]: both account ids, both token ids, both amounts
+//! - [`L2Tx::Nop`]: none
+//!
+//! `L2Block::detail` lists the same transactions in the [`L2Tx`] JSON format under
+//! `"txs"`, and [`check_block`] compares the two.
+// TODO: check the widths and the field order against the block circuit and replace the
+// hand-assembled bytes of `test_block_layout` with the `raw_public_data` of real blocks.
+use serde::{Deserialize, Serialize};
+
+use crate::serde::FloatsParts;
+use crate::types::{BitReader, BitWriter, Float40, PackError};
+
+/// Bits of the transaction type tag.
+pub const TX_TYPE_BITS: usize = 8;
+/// Bits of an account id.
+pub const ACCOUNT_ID_BITS: usize = 32;
+/// Bits of a token id.
+pub const TOKEN_ID_BITS: usize = 16;
+/// Bits of an amount, the whole [`Float40`] encoding.
+pub const AMOUNT_BITS: usize = Float40::ENCODED_BYTES * 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PubdataError {
+    #[error("unknown tx type {0}")]
+    UnknownTxType(u8),
+    #[error(transparent)]
+    Pack(#[from] PackError),
+    #[error("{0} is missing")]
+    Missing(&'static str),
+    #[error("public data differs from the block detail at tx {0}")]
+    Mismatch(usize),
+    #[cfg(feature = "serde_json")]
+    #[error(transparent)]
+    Detail(#[from] serde_json::Error),
+}
+
+type Result<T, E = PubdataError> = std::result::Result<T, E>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TxType {
+    Nop = 0,
+    Deposit = 1,
+    Transfer = 2,
+    Withdraw = 3,
+    SpotTrade = 4,
+    FullExit = 5,
+}
+
+impl TxType {
+    pub fn from_u8(tag: u8) -> Result<Self> {
+        Ok(match tag {
+            0 => TxType::Nop,
+            1 => TxType::Deposit,
+            2 => TxType::Transfer,
+            3 => TxType::Withdraw,
+            4 => TxType::SpotTrade,
+            5 => TxType::FullExit,
+            _ => return Err(PubdataError::UnknownTxType(tag)),
+        })
+    }
+}
+
+/// A transaction as it appears in the public data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum L2Tx {
+    Nop,
+    Deposit {
+        account_id: u32,
+        token_id: u16,
+        #[serde(with = "FloatsParts")]
+        amount: Float40,
+    },
+    Transfer {
+        from: u32,
+        to: u32,
+        token_id: u16,
+        #[serde(with = "FloatsParts")]
+        amount: Float40,
+    },
+    Withdraw {
+        account_id: u32,
+        token_id: u16,
+        #[serde(with = "FloatsParts")]
+        amount: Float40,
+    },
+    SpotTrade {
+        order1_account_id: u32,
+        order2_account_id: u32,
+        token_id_1to2: u16,
+        token_id_2to1: u16,
+        #[serde(with = "FloatsParts")]
+        amount_1to2: Float40,
+        #[serde(with = "FloatsParts")]
+        amount_2to1: Float40,
+    },
+    FullExit {
+        account_id: u32,
+        token_id: u16,
+        #[serde(with = "FloatsParts")]
+        amount: Float40,
+    },
+}
+
+impl L2Tx {
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            L2Tx::Nop => TxType::Nop,
+            L2Tx::Deposit { .. } => TxType::Deposit,
+            L2Tx::Transfer { .. } => TxType::Transfer,
+            L2Tx::Withdraw { .. } => TxType::Withdraw,
+            L2Tx::SpotTrade { .. } => TxType::SpotTrade,
+            L2Tx::FullExit { .. } => TxType::FullExit,
+        }
+    }
+
+    pub fn write(&self, writer: &mut BitWriter) -> Result<()> {
+        writer.write_uint(self.tx_type() as u8, TX_TYPE_BITS)?;
+        match *self {
+            L2Tx::Nop => {}
+            L2Tx::Deposit {
+                account_id,
+                token_id,
+                amount,
+            }
+            | L2Tx::Withdraw {
+                account_id,
+                token_id,
+                amount,
+            }
+            | L2Tx::FullExit {
+                account_id,
+                token_id,
+                amount,
+            } => {
+                writer
+                    .write_uint(account_id, ACCOUNT_ID_BITS)?
+                    .write_uint(token_id, TOKEN_ID_BITS)?
+                    .write_floats(amount, AMOUNT_BITS)?;
+            }
+            L2Tx::Transfer {
+                from,
+                to,
+                token_id,
+                amount,
+            } => {
+                writer
+                    .write_uint(from, ACCOUNT_ID_BITS)?
+                    .write_uint(to, ACCOUNT_ID_BITS)?
+                    .write_uint(token_id, TOKEN_ID_BITS)?
+                    .write_floats(amount, AMOUNT_BITS)?;
+            }
+            L2Tx::SpotTrade {
+                order1_account_id,
+                order2_account_id,
+                token_id_1to2,
+                token_id_2to1,
+                amount_1to2,
+                amount_2to1,
+            } => {
+                writer
+                    .write_uint(order1_account_id, ACCOUNT_ID_BITS)?
+                    .write_uint(order2_account_id, ACCOUNT_ID_BITS)?
+                    .write_uint(token_id_1to2, TOKEN_ID_BITS)?
+                    .write_uint(token_id_2to1, TOKEN_ID_BITS)?
+                    .write_floats(amount_1to2, AMOUNT_BITS)?
+                    .write_floats(amount_2to1, AMOUNT_BITS)?;
+            }
+        }
+        writer.pad_to_byte();
+        Ok(())
+    }
+
+    pub fn read(reader: &mut BitReader) -> Result<Self> {
+        let tx_type = TxType::from_u8(reader.read_u64(TX_TYPE_BITS)? as u8)?;
+        let tx = match tx_type {
+            TxType::Nop => L2Tx::Nop,
+            TxType::Deposit => L2Tx::Deposit {
+                account_id: read_account_id(reader)?,
+                token_id: read_token_id(reader)?,
+                amount: reader.read_floats(AMOUNT_BITS)?,
+            },
+            TxType::Transfer => L2Tx::Transfer {
+                from: read_account_id(reader)?,
+                to: read_account_id(reader)?,
+                token_id: read_token_id(reader)?,
+                amount: reader.read_floats(AMOUNT_BITS)?,
+            },
+            TxType::Withdraw => L2Tx::Withdraw {
+                account_id: read_account_id(reader)?,
+                token_id: read_token_id(reader)?,
+                amount: reader.read_floats(AMOUNT_BITS)?,
+            },
+            TxType::SpotTrade => L2Tx::SpotTrade {
+                order1_account_id: read_account_id(reader)?,
+                order2_account_id: read_account_id(reader)?,
+                token_id_1to2: read_token_id(reader)?,
+                token_id_2to1: read_token_id(reader)?,
+                amount_1to2: reader.read_floats(AMOUNT_BITS)?,
+                amount_2to1: reader.read_floats(AMOUNT_BITS)?,
+            },
+            TxType::FullExit => L2Tx::FullExit {
+                account_id: read_account_id(reader)?,
+                token_id: read_token_id(reader)?,
+                amount: reader.read_floats(AMOUNT_BITS)?,
+            },
+        };
+        reader.skip_to_byte()?;
+        Ok(tx)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut writer = BitWriter::new();
+        self.write(&mut writer)?;
+        Ok(writer.to_bytes())
+    }
+}
+
+fn read_account_id(reader: &mut BitReader) -> Result<u32> {
+    Ok(reader.read_u64(ACCOUNT_ID_BITS)? as u32)
+}
+
+fn read_token_id(reader: &mut BitReader) -> Result<u16> {
+    Ok(reader.read_u64(TOKEN_ID_BITS)? as u16)
+}
+
+/// Encode the public data of a block.
+pub fn encode_block(txs: &[L2Tx]) -> Result<Vec<u8>> {
+    let mut writer = BitWriter::new();
+    for tx in txs {
+        tx.write(&mut writer)?;
+    }
+    Ok(writer.to_bytes())
+}
+
+/// Decode the public data of a block, trailing zero padding included as [`L2Tx::Nop`]s.
+pub fn decode_block(data: &[u8]) -> Result<Vec<L2Tx>> {
+    let mut reader = BitReader::new(data);
+    let mut txs = Vec::new();
+    while reader.remaining() > 0 {
+        txs.push(L2Tx::read(&mut reader)?);
+    }
+    Ok(txs)
+}
+
+/// Check `data` decodes to `txs`, ignoring trailing [`L2Tx::Nop`] padding on both sides.
+pub fn check_block(data: &[u8], txs: &[L2Tx]) -> Result<()> {
+    fn trim(txs: &[L2Tx]) -> &[L2Tx] {
+        let len = txs
+            .iter()
+            .rposition(|tx| *tx != L2Tx::Nop)
+            .map_or(0, |i| i + 1);
+        &txs[..len]
+    }
+
+    let decoded = decode_block(data)?;
+    let (decoded, txs) = (trim(&decoded), trim(txs));
+    match decoded.iter().zip(txs).position(|(a, b)| a != b) {
+        Some(index) => Err(PubdataError::Mismatch(index)),
+        None if decoded.len() != txs.len() => {
+            Err(PubdataError::Mismatch(decoded.len().min(txs.len())))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(exponent: u8, significand: i64) -> Float40 {
+        Float40 {
+            exponent,
+            significand,
+        }
+    }
+
+    #[test]
+    fn test_block_round_trip() {
+        let txs = vec![
+            L2Tx::Deposit {
+                account_id: 1,
+                token_id: 2,
+                amount: amount(18, 5),
+            },
+            L2Tx::Transfer {
+                from: 1,
+                to: 3,
+                token_id: 2,
+                amount: amount(17, 25),
+            },
+            L2Tx::SpotTrade {
+                order1_account_id: 1,
+                order2_account_id: 3,
+                token_id_1to2: 2,
+                token_id_2to1: 0,
+                amount_1to2: amount(16, 1),
+                amount_2to1: amount(4, 34359738367 / 2),
+            },
+            L2Tx::Withdraw {
+                account_id: 3,
+                token_id: 2,
+                amount: amount(0, 7),
+            },
+            L2Tx::FullExit {
+                account_id: 1,
+                token_id: 0,
+                amount: amount(0, 0),
+            },
+            L2Tx::Nop,
+        ];
+        let data = encode_block(&txs).unwrap();
+        assert_eq!(data.len(), 12 + 16 + 23 + 12 + 12 + 1);
+        assert_eq!(&data[..5], &[1, 0, 0, 0, 1]);
+        assert_eq!(decode_block(&data).unwrap(), txs);
+
+        // zero padding decodes as nops
+        let mut padded = data.clone();
+        padded.resize(data.len() + 3, 0);
+        let decoded = decode_block(&padded).unwrap();
+        assert_eq!(decoded.len(), txs.len() + 3);
+        assert_eq!(decoded[txs.len()..], [L2Tx::Nop, L2Tx::Nop, L2Tx::Nop]);
+
+        assert!(matches!(
+            decode_block(&[9]),
+            Err(PubdataError::UnknownTxType(9))
+        ));
+        assert!(decode_block(&data[..data.len() - 2]).is_err());
+    }
+
+    // Assembled by hand from the layout in the module docs, not by `BitWriter`.
+    #[test]
+    fn test_block_layout() {
+        #[rustfmt::skip]
+        let data = [
+            // deposit: account 1, token 2, 5 * 10^18
+            0x01, 0, 0, 0, 1, 0, 2, 0x90, 0, 0, 0, 0x05,
+            // transfer: 1 to 3, token 2, 25 * 10^17
+            0x02, 0, 0, 0, 1, 0, 0, 0, 3, 0, 2, 0x88, 0, 0, 0, 0x19,
+            // withdraw: account 3, token 2, -7 * 10^2 in two's complement
+            0x03, 0, 0, 0, 3, 0, 2, 0x17, 0xff, 0xff, 0xff, 0xf9,
+            // nop
+            0x00,
+        ];
+        let txs = vec![
+            L2Tx::Deposit {
+                account_id: 1,
+                token_id: 2,
+                amount: amount(18, 5),
+            },
+            L2Tx::Transfer {
+                from: 1,
+                to: 3,
+                token_id: 2,
+                amount: amount(17, 25),
+            },
+            L2Tx::Withdraw {
+                account_id: 3,
+                token_id: 2,
+                amount: amount(2, -7),
+            },
+            L2Tx::Nop,
+        ];
+        assert_eq!(decode_block(&data).unwrap(), txs);
+        assert_eq!(encode_block(&txs).unwrap(), data);
+    }
+
+    #[test]
+    fn test_check_block() {
+        let txs = vec![
+            L2Tx::Deposit {
+                account_id: 1,
+                token_id: 2,
+                amount: amount(18, 5),
+            },
+            L2Tx::Withdraw {
+                account_id: 1,
+                token_id: 2,
+                amount: amount(17, 5),
+            },
+        ];
+        let mut data = encode_block(&txs).unwrap();
+        check_block(&data, &txs).unwrap();
+        data.resize(data.len() + 4, 0);
+        check_block(&data, &txs).unwrap();
+        let mut padded = txs.clone();
+        padded.push(L2Tx::Nop);
+        check_block(&data, &padded).unwrap();
+
+        assert!(matches!(
+            check_block(&data, &txs[..1]),
+            Err(PubdataError::Mismatch(1))
+        ));
+        let mut changed = txs.clone();
+        changed[1] = L2Tx::Withdraw {
+            account_id: 1,
+            token_id: 2,
+            amount: amount(16, 5),
+        };
+        assert!(matches!(
+            check_block(&data, &changed),
+            Err(PubdataError::Mismatch(1))
+        ));
+        assert!(matches!(
+            check_block(&[], &txs),
+            Err(PubdataError::Mismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_tx_json() {
+        let tx = L2Tx::Deposit {
+            account_id: 1,
+            token_id: 2,
+            amount: amount(18, 5),
+        };
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "deposit",
+                "account_id": 1,
+                "token_id": 2,
+                "amount": {"exponent": 18, "significand": 5},
+            })
+        );
+        assert_eq!(serde_json::from_value::<L2Tx>(json).unwrap(), tx);
+    }
+}
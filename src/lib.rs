@@ -7,9 +7,11 @@ use once_cell::sync::Lazy;
 pub mod db;
 pub mod helper;
 pub mod l2;
+pub mod merkle;
 pub mod message;
 #[cfg(feature = "non-blocking-tracing")]
 pub mod non_blocking_tracing;
+pub mod poseidon;
 pub mod serde;
 pub mod types;
 pub mod utils;
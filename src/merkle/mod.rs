@@ -0,0 +1,18 @@
+//! Fixed-depth sparse Merkle trees over [`Fr`](crate::Fr) leaves, hashed with Poseidon.
+mod proof;
+mod tree;
+
+pub use proof::*;
+pub use tree::*;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MerkleError {
+    #[error("invalid tree depth {0}, expected 1 ~ {}", MerkleTree::MAX_DEPTH)]
+    InvalidDepth(usize),
+    #[error("leaf index {0} out of range for depth {1}")]
+    IndexOutOfRange(u32, usize),
+    #[error("leaf {0} is not empty")]
+    NonEmptyLeaf(u32),
+}
+
+type Result<T, E = MerkleError> = std::result::Result<T, E>;
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use super::{hash_pair, MerkleError, MerkleTree, Result};
+use crate::ff::Field;
+use crate::serde::FrStr;
+use crate::types::{Fr, FrExt};
+
+/// A Merkle path from a leaf to the root.
+///
+/// `siblings` and `path_index` both start at the leaf level. `path_index` holds the
+/// little-endian bits of the leaf index as field elements, so the proof serializes
+/// straight into circuit input JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    #[serde(with = "FrStr")]
+    pub leaf: Fr,
+    #[serde(with = "FrStr")]
+    pub path_index: Vec<Fr>,
+    #[serde(with = "FrStr")]
+    pub siblings: Vec<Fr>,
+}
+
+impl MerkleProof {
+    /// The leaf index encoded by `path_index`, `None` if it is malformed.
+    pub fn index(&self) -> Option<u32> {
+        if self.path_index.len() > MerkleTree::MAX_DEPTH {
+            return None;
+        }
+        self.path_index
+            .iter()
+            .enumerate()
+            .try_fold(0u32, |index, (level, bit)| {
+                bit.to_bool()
+                    .ok()
+                    .map(|bit| index | ((bit as u32) << level))
+            })
+    }
+
+    /// Recompute the root from the leaf and its siblings, `None` if the proof is malformed
+    /// or is not exactly `depth` levels long.
+    ///
+    /// The depth must come from the verifier: a shorter path would pass an inner node, or
+    /// the root itself, off as a leaf.
+    pub fn compute_root(&self, depth: usize) -> Option<Fr> {
+        if depth == 0
+            || depth > MerkleTree::MAX_DEPTH
+            || self.siblings.len() != depth
+            || self.path_index.len() != depth
+        {
+            return None;
+        }
+        self.siblings.iter().zip(self.path_index.iter()).try_fold(
+            self.leaf,
+            |node, (sibling, bit)| {
+                Some(match bit.to_bool().ok()? {
+                    false => hash_pair(&node, sibling),
+                    true => hash_pair(sibling, &node),
+                })
+            },
+        )
+    }
+
+    /// Check the proof shows `leaf` at `path_index` in the tree of `depth` and `root`.
+    pub fn verify(&self, root: &Fr, depth: usize) -> bool {
+        self.compute_root(depth).as_ref() == Some(root)
+    }
+
+    /// Check the proof shows an empty leaf at `path_index` in the tree of `depth` and `root`.
+    pub fn verify_exclusion(&self, root: &Fr, depth: usize) -> bool {
+        self.leaf.is_zero() && self.verify(root, depth)
+    }
+}
+
+impl MerkleTree {
+    /// Inclusion proof for the leaf at `index`, empty or not.
+    pub fn prove(&self, index: u32) -> Result<MerkleProof> {
+        if (index as u64) >> self.depth() != 0 {
+            return Err(MerkleError::IndexOutOfRange(index, self.depth()));
+        }
+
+        let mut path_index = Vec::with_capacity(self.depth());
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut node_index = index;
+        for level in 0..self.depth() {
+            path_index.push(Fr::from_u32(node_index & 1));
+            siblings.push(self.node_unchecked(level, node_index ^ 1));
+            node_index >>= 1;
+        }
+
+        Ok(MerkleProof {
+            leaf: self.get(index),
+            path_index,
+            siblings,
+        })
+    }
+
+    /// Exclusion proof showing the leaf at `index` is empty.
+    pub fn prove_exclusion(&self, index: u32) -> Result<MerkleProof> {
+        let proof = self.prove(index)?;
+        if proof.leaf.is_zero() {
+            Ok(proof)
+        } else {
+            Err(MerkleError::NonEmptyLeaf(index))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inclusion_proof() {
+        let tree = MerkleTree::from_leaves(
+            5,
+            vec![
+                (3, Fr::from_u32(3)),
+                (17, Fr::from_u32(17)),
+                (31, Fr::from_u32(31)),
+            ],
+        )
+        .unwrap();
+        let proof = tree.prove(17).unwrap();
+        assert_eq!(proof.index(), Some(17));
+        assert_eq!(proof.leaf, Fr::from_u32(17));
+        assert!(proof.verify(&tree.root(), 5));
+        assert!(!proof.verify_exclusion(&tree.root(), 5));
+
+        let mut forged = proof.clone();
+        forged.leaf = Fr::from_u32(18);
+        assert!(!forged.verify(&tree.root(), 5));
+        let mut forged = proof.clone();
+        forged.path_index[0] = Fr::from_u32(2);
+        assert_eq!(forged.index(), None);
+        assert!(!forged.verify(&tree.root(), 5));
+        let mut forged = proof.clone();
+        forged.siblings.pop();
+        assert!(!forged.verify(&tree.root(), 5));
+
+        // a shortened path passes an inner node off as a leaf
+        let short = MerkleProof {
+            leaf: tree.node(1, 17 >> 1).unwrap(),
+            path_index: proof.path_index[1..].to_vec(),
+            siblings: proof.siblings[1..].to_vec(),
+        };
+        assert_eq!(short.compute_root(4), Some(tree.root()));
+        assert!(!short.verify(&tree.root(), 5));
+        // and an empty path the root itself
+        let empty = MerkleProof {
+            leaf: tree.root(),
+            path_index: vec![],
+            siblings: vec![],
+        };
+        assert!(!empty.verify(&tree.root(), 5));
+        assert!(!empty.verify(&tree.root(), 0));
+        assert!(!proof.verify(&tree.root(), 6));
+
+        assert!(tree.prove(32).is_err());
+    }
+
+    #[test]
+    fn test_exclusion_proof() {
+        let tree = MerkleTree::from_leaves(5, vec![(3, Fr::from_u32(3))]).unwrap();
+        let proof = tree.prove_exclusion(4).unwrap();
+        assert!(proof.verify_exclusion(&tree.root(), 5));
+        assert!(matches!(
+            tree.prove_exclusion(3),
+            Err(MerkleError::NonEmptyLeaf(3))
+        ));
+    }
+
+    #[test]
+    fn test_proof_json() {
+        let tree = MerkleTree::from_leaves(2, vec![(1, Fr::from_u32(5))]).unwrap();
+        let proof = tree.prove(1).unwrap();
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["leaf"], "5");
+        assert_eq!(json["path_index"], serde_json::json!(["1", "0"]));
+        assert_eq!(json["siblings"][0], "0");
+        let decoded: MerkleProof = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(decoded, proof);
+
+        // a sibling outside the field is rejected before it reaches `verify`
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        for sibling in [modulus, "-1"] {
+            let mut forged = json.clone();
+            forged["siblings"][0] = serde_json::json!(sibling);
+            assert!(serde_json::from_value::<MerkleProof>(forged).is_err());
+        }
+        let mut forged = json;
+        forged["leaf"] = serde_json::json!(modulus);
+        assert!(serde_json::from_value::<MerkleProof>(forged).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeSet;
+
+use super::{MerkleError, Result};
+use crate::ff::Field;
+use crate::types::{Fr, FrExt, MerkleValueMapType};
+
+/// Hash of an inner node, `Poseidon(left, right)`.
+pub fn hash_pair(left: &Fr, right: &Fr) -> Fr {
+    Fr::hash(&[*left, *right])
+}
+
+/// A sparse Merkle tree of fixed depth, with empty leaves set to zero.
+///
+/// Only non-default nodes are stored; the roots of empty subtrees are computed once
+/// when the tree is created.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    depth: usize,
+    // default_nodes[level] is the root of an empty subtree of that height
+    default_nodes: Vec<Fr>,
+    // nodes[0] holds the leaves and nodes[depth] the root
+    nodes: Vec<MerkleValueMapType<u32, Fr>>,
+}
+
+impl MerkleTree {
+    pub const MAX_DEPTH: usize = 32;
+
+    pub fn new(depth: usize) -> Result<Self> {
+        if depth == 0 || depth > Self::MAX_DEPTH {
+            return Err(MerkleError::InvalidDepth(depth));
+        }
+
+        let mut default_nodes = Vec::with_capacity(depth + 1);
+        default_nodes.push(Fr::zero());
+        for level in 0..depth {
+            let child = default_nodes[level];
+            default_nodes.push(hash_pair(&child, &child));
+        }
+
+        Ok(Self {
+            depth,
+            default_nodes,
+            nodes: vec![MerkleValueMapType::default(); depth + 1],
+        })
+    }
+
+    /// Build a tree from its non-empty leaves.
+    pub fn from_leaves<I>(depth: usize, leaves: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (u32, Fr)>,
+    {
+        let mut tree = Self::new(depth)?;
+        tree.batch_update(leaves)?;
+        Ok(tree)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn root(&self) -> Fr {
+        self.node_unchecked(self.depth, 0)
+    }
+
+    /// The non-empty leaves of the tree.
+    pub fn leaves(&self) -> &MerkleValueMapType<u32, Fr> {
+        &self.nodes[0]
+    }
+
+    pub fn get(&self, index: u32) -> Fr {
+        self.node_unchecked(0, index)
+    }
+
+    /// The node at `index` of `level`, where level 0 are the leaves, `None` if `level` is
+    /// above the root or `index` beyond the width of the level.
+    pub fn node(&self, level: usize, index: u32) -> Option<Fr> {
+        if level > self.depth || (index as u64) >> (self.depth - level) != 0 {
+            return None;
+        }
+        Some(self.node_unchecked(level, index))
+    }
+
+    /// The root of an empty subtree of height `level`, `None` if `level` is above the root.
+    pub fn default_node(&self, level: usize) -> Option<Fr> {
+        self.default_nodes.get(level).copied()
+    }
+
+    /// Like [`MerkleTree::node`], for `level` known to be at most the depth.
+    pub(super) fn node_unchecked(&self, level: usize, index: u32) -> Fr {
+        self.nodes[level]
+            .get(&index)
+            .copied()
+            .unwrap_or(self.default_nodes[level])
+    }
+
+    /// Insert or update a leaf and recompute its path to the root.
+    pub fn set(&mut self, index: u32, value: Fr) -> Result<()> {
+        self.check_index(index)?;
+        self.set_node(0, index, value);
+
+        let mut index = index;
+        for level in 1..=self.depth {
+            index >>= 1;
+            self.update_node(level, index);
+        }
+        Ok(())
+    }
+
+    /// Update many leaves at once, recomputing each affected inner node only once.
+    /// Nothing is updated if any index is out of range.
+    pub fn batch_update<I>(&mut self, updates: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (u32, Fr)>,
+    {
+        let updates: Vec<(u32, Fr)> = updates.into_iter().collect();
+        for (index, _) in &updates {
+            self.check_index(*index)?;
+        }
+
+        let mut dirty = BTreeSet::new();
+        for (index, value) in updates {
+            self.set_node(0, index, value);
+            dirty.insert(index);
+        }
+        for level in 1..=self.depth {
+            dirty = dirty.into_iter().map(|index| index >> 1).collect();
+            for index in &dirty {
+                self.update_node(level, *index);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_index(&self, index: u32) -> Result<()> {
+        if (index as u64) >> self.depth != 0 {
+            Err(MerkleError::IndexOutOfRange(index, self.depth))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn set_node(&mut self, level: usize, index: u32, value: Fr) {
+        if value == self.default_nodes[level] {
+            self.nodes[level].remove(&index);
+        } else {
+            self.nodes[level].insert(index, value);
+        }
+    }
+
+    fn update_node(&mut self, level: usize, index: u32) {
+        let left = self.node_unchecked(level - 1, index << 1);
+        let right = self.node_unchecked(level - 1, (index << 1) + 1);
+        self.set_node(level, index, hash_pair(&left, &right));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::FrStr;
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = MerkleTree::new(3).unwrap();
+        let mut root = Fr::zero();
+        for _ in 0..3 {
+            root = hash_pair(&root, &root);
+        }
+        assert_eq!(tree.root(), root);
+        assert_eq!(tree.get(7), Fr::zero());
+        assert_eq!(tree.node(3, 0), Some(root));
+        assert_eq!(tree.default_node(3), Some(root));
+        assert_eq!(tree.node(4, 0), None);
+        assert_eq!(tree.node(1, 4), None);
+        assert_eq!(tree.default_node(4), None);
+        assert!(MerkleTree::new(0).is_err());
+        assert!(MerkleTree::new(33).is_err());
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let mut tree = MerkleTree::new(2).unwrap();
+        tree.set(1, Fr::from_u32(1)).unwrap();
+        tree.set(2, Fr::from_u32(2)).unwrap();
+        assert_eq!(tree.get(1), Fr::from_u32(1));
+        let left = hash_pair(&Fr::zero(), &Fr::from_u32(1));
+        let right = hash_pair(&Fr::from_u32(2), &Fr::zero());
+        assert_eq!(tree.root(), hash_pair(&left, &right));
+
+        tree.set(1, Fr::zero()).unwrap();
+        tree.set(2, Fr::zero()).unwrap();
+        assert_eq!(tree.root(), MerkleTree::new(2).unwrap().root());
+        assert!(tree.leaves().is_empty());
+        assert!(tree.nodes.iter().all(|level| level.is_empty()));
+
+        assert!(matches!(
+            tree.set(4, Fr::one()),
+            Err(MerkleError::IndexOutOfRange(4, 2))
+        ));
+        let mut full = MerkleTree::new(32).unwrap();
+        full.set(u32::MAX, Fr::one()).unwrap();
+        assert_eq!(full.get(u32::MAX), Fr::one());
+    }
+
+    #[test]
+    fn test_batch_update() {
+        let updates: Vec<(u32, Fr)> = (0..50).map(|i| (i * 7, Fr::from_u32(i + 1))).collect();
+        let mut sequential = MerkleTree::new(10).unwrap();
+        for (index, value) in updates.clone() {
+            sequential.set(index, value).unwrap();
+        }
+        let batch = MerkleTree::from_leaves(10, updates.clone()).unwrap();
+        assert_eq!(batch.root(), sequential.root());
+
+        let mut tree = batch.clone();
+        let result = tree.batch_update(vec![(0, Fr::zero()), (1024, Fr::one())]);
+        assert!(result.is_err());
+        assert_eq!(tree.root(), batch.root());
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct State {
+            #[serde(with = "FrStr")]
+            tree: MerkleTree,
+        }
+
+        let tree = MerkleTree::from_leaves(8, vec![(3, Fr::from_u32(3)), (200, Fr::from_u32(200))])
+            .unwrap();
+        let json = serde_json::to_string(&State { tree: tree.clone() }).unwrap();
+        let state: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.tree.depth(), 8);
+        assert_eq!(state.tree.root(), tree.root());
+
+        // leaves outside the field are errors, not panics
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        for leaf in [modulus, "-1"] {
+            let json = serde_json::json!({ "tree": { "depth": 8, "leaves": { "3": leaf } } });
+            assert!(serde_json::from_value::<State>(json).is_err());
+        }
+    }
+}
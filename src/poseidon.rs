@@ -0,0 +1,105 @@
+//! Variable-length Poseidon hashing on top of [`POSEIDON_HASHER`].
+//!
+//! [`POSEIDON_HASHER`] only accepts a handful of inputs per call, so longer inputs are
+//! absorbed in chunks of [`RATE`] elements, chaining the state through each call:
+//!
+//! ```text
+//! state_0 = iv
+//! state_i = Poseidon(state_{i-1}, chunk_i[0], ..., chunk_i[k-1])    (1 <= k <= RATE)
+//! hash    = state_n
+//! ```
+//!
+//! `iv` is `len + domain * 2^64`, where `len` is the number of inputs (elements for
+//! [`hash`], bytes for [`hash_bytes`]) and `domain` is [`DOMAIN_FIELDS`] or
+//! [`DOMAIN_BYTES`]. The last chunk is not padded. An empty input is hashed as
+//! `Poseidon(iv)`.
+//!
+//! Byte strings are packed by [`pack_bytes`] into big-endian field elements of
+//! [`BYTES_PER_ELEMENT`] bytes each, the last element holding the remaining bytes.
+use crate::types::{Fr, FrExt};
+use crate::POSEIDON_HASHER;
+
+/// Number of elements absorbed per Poseidon call, besides the chained state.
+pub const RATE: usize = 5;
+/// Number of bytes packed into one field element.
+pub const BYTES_PER_ELEMENT: usize = 31;
+/// Domain tag for hashing field elements.
+pub const DOMAIN_FIELDS: u64 = 0;
+/// Domain tag for hashing byte strings.
+pub const DOMAIN_BYTES: u64 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PoseidonError {
+    #[error("poseidon hasher error: {0}")]
+    Hasher(String),
+}
+
+type Result<T, E = PoseidonError> = std::result::Result<T, E>;
+
+/// Hash any number of field elements.
+pub fn hash(inputs: &[Fr]) -> Result<Fr> {
+    sponge(iv(inputs.len(), DOMAIN_FIELDS), inputs)
+}
+
+/// Hash a byte string, packed by [`pack_bytes`].
+pub fn hash_bytes(data: &[u8]) -> Result<Fr> {
+    sponge(iv(data.len(), DOMAIN_BYTES), &pack_bytes(data))
+}
+
+/// Pack bytes into big-endian field elements of [`BYTES_PER_ELEMENT`] bytes each.
+pub fn pack_bytes(data: &[u8]) -> Vec<Fr> {
+    data.chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| Fr::from_slice(chunk).unwrap())
+        .collect()
+}
+
+fn iv(len: usize, domain: u64) -> Fr {
+    Fr::from_u128(((domain as u128) << 64) + len as u128)
+}
+
+fn sponge(iv: Fr, inputs: &[Fr]) -> Result<Fr> {
+    if inputs.is_empty() {
+        return poseidon(vec![iv]);
+    }
+    inputs.chunks(RATE).try_fold(iv, |state, chunk| {
+        let mut round = Vec::with_capacity(RATE + 1);
+        round.push(state);
+        round.extend_from_slice(chunk);
+        poseidon(round)
+    })
+}
+
+fn poseidon(inputs: Vec<Fr>) -> Result<Fr> {
+    POSEIDON_HASHER.hash(inputs).map_err(PoseidonError::Hasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_chain() {
+        let inputs: Vec<Fr> = (1..=7).map(Fr::from_u32).collect();
+        let state = Fr::hash(&[vec![Fr::from_u32(7)], inputs[..5].to_vec()].concat());
+        let expected = Fr::hash(&[vec![state], inputs[5..].to_vec()].concat());
+        assert_eq!(hash(&inputs).unwrap(), expected);
+
+        assert_eq!(hash(&[]).unwrap(), Fr::hash(&[Fr::from_u32(0)]));
+        assert_ne!(hash(&inputs[..6]).unwrap(), hash(&inputs[..5]).unwrap());
+
+        let long: Vec<Fr> = (0..100).map(Fr::from_u32).collect();
+        assert!(hash(&long).is_ok());
+    }
+
+    #[test]
+    fn test_hash_bytes() {
+        let data: Vec<u8> = (0..100).collect();
+        let packed = pack_bytes(&data);
+        assert_eq!(packed.len(), 4);
+        assert_eq!(packed[3], Fr::from_slice(&data[93..]).unwrap());
+
+        assert_ne!(hash_bytes(&[0]).unwrap(), hash_bytes(&[0, 0]).unwrap());
+        assert_ne!(hash_bytes(&[1]).unwrap(), hash(&[Fr::from_u32(1)]).unwrap());
+        assert!(hash_bytes(&data).is_ok());
+    }
+}
@@ -5,6 +5,7 @@
 //! use serde::{Serialize, Deserialize};
 //! use fluidex_common::serde::*;
 //! use fluidex_common::Fr;
+//! use fluidex_common::types::FieldElement;
 //!
 //! #[derive(Debug, Serialize, Deserialize)]
 //! struct Message {
@@ -14,8 +15,18 @@
 //!     from: Fr,
 //!     #[serde(with = "FrStr")]
 //!     to: Fr,
+//!     #[serde(with = "FrStr")]
+//!     amount: FieldElement,
 //! }
 //! ```
+//!
+//! Floats (e.g. `Float40`, `Float864`) can be carried either as the hex of
+//! their packed bytes (`FloatsHex`), as the packed integer in a decimal
+//! string (`FloatsInt`) or as an `{exponent, significand}` struct
+//! (`FloatsParts`).
+//!
+//! Babyjubjub pubkeys and signatures are carried as packed hex strings with `PubkeyHex` and
+//! `SignatureHex`, which also accept the uncompressed `{x, y}` and `{r_b8: {x, y}, s}` forms.
 use core::convert::TryInto;
 use core::fmt;
 use core::marker::PhantomData;
@@ -23,10 +34,16 @@ use core::str::FromStr;
 
 use num_bigint::BigInt;
 use serde::de::{Deserializer, Error, Unexpected, Visitor};
-use serde::ser::{SerializeMap, Serializer};
+use serde::ser::Error as SerError;
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 
-use crate::types::{Fr, FrExt, MerkleValueMapType};
+use crate::merkle::MerkleTree;
+use crate::num_traits::ToPrimitive;
+use crate::types::{
+    check_subgroup, FieldElement, Floats, Fr, FrExt, MerkleValueMapType, Pubkey, PubkeyExt,
+    Signature, SignatureExt, Significand, SUBORDER,
+};
 use std::hash::Hash;
 
 /// Helper trait add serde support to `[u8; N]` using hex encoding.
@@ -59,6 +76,56 @@ pub trait FrStr<'de>: Sized {
         D: Deserializer<'de>;
 }
 
+/// Helper trait add serde support to floats using hex of the packed encoding.
+pub trait FloatsHex<'de>: Sized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Helper trait add serde support to floats using the packed encoding as a decimal integer.
+pub trait FloatsInt<'de>: Sized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Helper trait add serde support to floats as an `{exponent, significand}` struct.
+pub trait FloatsParts<'de>: Sized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Helper trait add serde support to [`Pubkey`] using packed hex encoding.
+pub trait PubkeyHex<'de>: Sized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Helper trait add serde support to [`Signature`] using packed hex encoding.
+pub trait SignatureHex<'de>: Sized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
 impl<'de, const N: usize> HexArray<'de> for [u8; N] {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -166,6 +233,50 @@ where
     }
 }
 
+impl<'de> FrBytes<'de> for Vec<Fr> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Wrapper(#[serde(with = "FrBytes")] Fr);
+
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for v in self.iter() {
+            seq.serialize_element(&Wrapper(*v))?;
+        }
+        seq.end()
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "FrBytes")] Fr);
+
+        let v = Vec::<Wrapper>::deserialize(deserializer)?;
+
+        Ok(v.into_iter().map(|Wrapper(v)| v).collect())
+    }
+}
+
+impl<'de> FrBytes<'de> for FieldElement {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FrBytes::serialize(&self.0, serializer)
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Fr as FrBytes<'de>>::deserialize(deserializer).map(FieldElement)
+    }
+}
+
 impl<'de> FrStr<'de> for Fr {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -192,7 +303,8 @@ impl<'de> FrStr<'de> for Fr {
                 E: Error,
             {
                 if let Ok(fr) = BigInt::from_str(v) {
-                    Ok(Fr::from_bigint(fr))
+                    Fr::try_from_bigint(fr)
+                        .map_err(|_| Error::invalid_value(Unexpected::Str(v), &self))
                 } else {
                     Err(Error::invalid_type(Unexpected::Str(v), &self))
                 }
@@ -203,6 +315,50 @@ impl<'de> FrStr<'de> for Fr {
     }
 }
 
+impl<'de> FrStr<'de> for FieldElement {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FrStr::serialize(&self.0, serializer)
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Fr as FrStr<'de>>::deserialize(deserializer).map(FieldElement)
+    }
+}
+
+impl<'de> FrStr<'de> for Vec<Fr> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Wrapper(#[serde(with = "FrStr")] Fr);
+
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for v in self.iter() {
+            seq.serialize_element(&Wrapper(*v))?;
+        }
+        seq.end()
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "FrStr")] Fr);
+
+        let v = Vec::<Wrapper>::deserialize(deserializer)?;
+
+        Ok(v.into_iter().map(|Wrapper(v)| v).collect())
+    }
+}
+
 impl<'de, K> FrStr<'de> for MerkleValueMapType<K, Fr>
 where
     K: Eq + Hash + Serialize + Deserialize<'de>,
@@ -233,3 +389,452 @@ where
         Ok(map.into_iter().map(|(k, Wrapper(v))| (k, v)).collect())
     }
 }
+
+impl<'de> FrBytes<'de> for MerkleTree {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        struct Leaves<'a>(&'a MerkleValueMapType<u32, Fr>);
+
+        impl Serialize for Leaves<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                FrBytes::serialize(self.0, serializer)
+            }
+        }
+
+        let mut state = serializer.serialize_struct("MerkleTree", 2)?;
+        state.serialize_field("depth", &self.depth())?;
+        state.serialize_field("leaves", &Leaves(self.leaves()))?;
+        state.end()
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            depth: usize,
+            #[serde(with = "FrBytes")]
+            leaves: MerkleValueMapType<u32, Fr>,
+        }
+
+        let Wrapper { depth, leaves } = Wrapper::deserialize(deserializer)?;
+        MerkleTree::from_leaves(depth, leaves).map_err(D::Error::custom)
+    }
+}
+
+impl<'de> FrStr<'de> for MerkleTree {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        struct Leaves<'a>(&'a MerkleValueMapType<u32, Fr>);
+
+        impl Serialize for Leaves<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                FrStr::serialize(self.0, serializer)
+            }
+        }
+
+        let mut state = serializer.serialize_struct("MerkleTree", 2)?;
+        state.serialize_field("depth", &self.depth())?;
+        state.serialize_field("leaves", &Leaves(self.leaves()))?;
+        state.end()
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            depth: usize,
+            #[serde(with = "FrStr")]
+            leaves: MerkleValueMapType<u32, Fr>,
+        }
+
+        let Wrapper { depth, leaves } = Wrapper::deserialize(deserializer)?;
+        MerkleTree::from_leaves(depth, leaves).map_err(D::Error::custom)
+    }
+}
+
+/// Left pad the big-endian bytes of a non-negative `bi` to `len` bytes.
+fn padded_bytes(bi: &BigInt, len: usize) -> Option<Vec<u8>> {
+    let (_, bytes) = bi.to_bytes_be();
+    if bytes.len() > len {
+        return None;
+    }
+    let mut ret = vec![0u8; len - bytes.len()];
+    ret.extend_from_slice(&bytes);
+    Some(ret)
+}
+
+/// Accepts a hex string, with or without `0x`, of exactly `len` bytes.
+struct PackedHexVisitor(usize);
+
+impl<'de> Visitor<'de> for PackedHexVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an hex encoded float of {} bytes", self.0)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        hex::decode(v.strip_prefix("0x").unwrap_or(v))
+            .ok()
+            .filter(|bytes| bytes.len() == self.0)
+            .ok_or_else(|| Error::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// Accepts the packed integer as a decimal string or as a plain number.
+struct PackedIntVisitor;
+
+impl<'de> Visitor<'de> for PackedIntVisitor {
+    type Value = BigInt;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a packed float as a decimal integer")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(BigInt::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        BigInt::from_str(v)
+            .ok()
+            .filter(|bi| bi.sign() != num_bigint::Sign::Minus)
+            .ok_or_else(|| Error::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Parts<T> {
+    exponent: u8,
+    significand: T,
+}
+
+impl<'de, T: Significand, const NBITS: usize, const LAYOUT: u8> FloatsHex<'de>
+    for Floats<T, NBITS, LAYOUT>
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bi = self.to_encoded_int().map_err(S::Error::custom)?;
+        let bytes = padded_bytes(&bi, Self::ENCODED_BYTES)
+            .ok_or_else(|| S::Error::custom("encoded float overflow"))?;
+        serializer.serialize_str(hex::encode(bytes).as_str())
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_str(PackedHexVisitor(Self::ENCODED_BYTES))?;
+        Self::decode(&bytes).map_err(D::Error::custom)
+    }
+}
+
+impl<'de, T: Significand, const NBITS: usize, const LAYOUT: u8> FloatsInt<'de>
+    for Floats<T, NBITS, LAYOUT>
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bi = self.to_encoded_int().map_err(S::Error::custom)?;
+        serializer.serialize_str(bi.to_str_radix(10).as_str())
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bi = deserializer.deserialize_any(PackedIntVisitor)?;
+        if bi.bits() > (Self::ENCODED_BYTES * 8) as u64 {
+            return Err(D::Error::custom(format!("encoded float {} overflow", bi)));
+        }
+        Self::from_encoded_bigint(bi).map_err(D::Error::custom)
+    }
+}
+
+impl<'de, T, const NBITS: usize, const LAYOUT: u8> FloatsParts<'de> for Floats<T, NBITS, LAYOUT>
+where
+    T: Significand + Serialize + Deserialize<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Parts {
+            exponent: self.exponent,
+            significand: self.significand,
+        }
+        .serialize(serializer)
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Parts {
+            exponent,
+            significand,
+        } = Parts::<T>::deserialize(deserializer)?;
+        if exponent > Self::MAX_EXPONENT {
+            return Err(D::Error::custom(format!("exponent {} too big", exponent)));
+        }
+        match significand.to_i128() {
+            Some(sig) if (Self::MIN_SIGNIFICAND..=Self::MAX_SIGNIFICAND).contains(&sig) => {
+                Ok(Self {
+                    exponent,
+                    significand,
+                })
+            }
+            _ => Err(D::Error::custom("significand out of range")),
+        }
+    }
+}
+
+/// A packed hex string or the uncompressed `{x, y}` form of a point.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PointRepr {
+    Hex(String),
+    Point {
+        #[serde(with = "FrStr")]
+        x: Fr,
+        #[serde(with = "FrStr")]
+        y: Fr,
+    },
+}
+
+impl PointRepr {
+    fn into_pubkey<E: Error>(self) -> Result<Pubkey, E> {
+        match self {
+            PointRepr::Hex(hex) => Pubkey::from_str(&hex)
+                .and_then(check_subgroup)
+                .map_err(E::custom),
+            PointRepr::Point { x, y } => Pubkey::from_ax_ay(&x, &y).map_err(E::custom),
+        }
+    }
+}
+
+impl<'de> PubkeyHex<'de> for Pubkey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_hex_string().as_str())
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        PointRepr::deserialize(deserializer)?.into_pubkey()
+    }
+}
+
+impl<'de> SignatureHex<'de> for Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_hex_string().as_str())
+    }
+
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum SignatureRepr {
+            Hex(String),
+            Parts { r_b8: PointRepr, s: String },
+        }
+
+        match SignatureRepr::deserialize(deserializer)? {
+            SignatureRepr::Hex(hex) => Signature::from_str(&hex).map_err(D::Error::custom),
+            SignatureRepr::Parts { r_b8, s } => {
+                let s = BigInt::from_str(&s)
+                    .ok()
+                    .filter(|s| s.sign() != num_bigint::Sign::Minus && *s < *SUBORDER)
+                    .ok_or_else(|| {
+                        D::Error::invalid_value(Unexpected::Str(&s), &"s below the subgroup order")
+                    })?;
+                Ok(Signature {
+                    r_b8: r_b8.into_pubkey()?,
+                    s,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ff::Field;
+    use crate::types::{Float40, Float864};
+    use babyjubjub_rs::PrivateKey;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        #[serde(with = "FloatsHex")]
+        hex: Float40,
+        #[serde(with = "FloatsInt")]
+        int: Float40,
+        #[serde(with = "FloatsParts")]
+        parts: Float864,
+    }
+
+    #[test]
+    fn test_floats_serde() {
+        let order = Order {
+            hex: Float40 {
+                exponent: 4,
+                significand: 123,
+            },
+            int: Float40 {
+                exponent: 0,
+                significand: 45,
+            },
+            parts: Float864 {
+                exponent: 3,
+                significand: 7,
+            },
+        };
+        let json = serde_json::to_string(&order).unwrap();
+        assert_eq!(
+            json,
+            r#"{"hex":"200000007b","int":"45","parts":{"exponent":3,"significand":7}}"#
+        );
+        assert_eq!(serde_json::from_str::<Order>(&json).unwrap(), order);
+
+        let json = r#"{"hex":"0x200000007b","int":45,"parts":{"exponent":3,"significand":7}}"#;
+        assert_eq!(serde_json::from_str::<Order>(json).unwrap(), order);
+
+        let json = r#"{"hex":"20000000","int":"45","parts":{"exponent":3,"significand":7}}"#;
+        assert!(serde_json::from_str::<Order>(json).is_err());
+        let json = r#"{"hex":"200000007b","int":"-45","parts":{"exponent":3,"significand":7}}"#;
+        assert!(serde_json::from_str::<Order>(json).is_err());
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Signed {
+        #[serde(with = "PubkeyHex")]
+        pubkey: Pubkey,
+        #[serde(with = "SignatureHex")]
+        signature: Signature,
+    }
+
+    #[test]
+    fn test_pubkey_signature_serde() {
+        let key = PrivateKey::import(vec![1u8; 32]).unwrap();
+        let signed = Signed {
+            pubkey: key.public(),
+            signature: key.sign(BigInt::from(42)).unwrap(),
+        };
+        let json = serde_json::to_value(&signed).unwrap();
+        let pubkey_hex = signed.pubkey.to_hex_string();
+        assert_eq!(json["pubkey"], serde_json::json!(pubkey_hex));
+        assert_eq!(json["signature"].as_str().unwrap().len(), 130);
+
+        let parsed: Signed = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.pubkey.compress(), signed.pubkey.compress());
+        assert_eq!(
+            parsed.signature.to_hex_string(),
+            signed.signature.to_hex_string()
+        );
+
+        let parsed: Signed = serde_json::from_value(serde_json::json!({
+            "pubkey": pubkey_hex.trim_start_matches("0x"),
+            "signature": {
+                "r_b8": {
+                    "x": signed.signature.r_b8.x.to_decimal_string(),
+                    "y": signed.signature.r_b8.y.to_decimal_string(),
+                },
+                "s": signed.signature.s.to_string(),
+            },
+        }))
+        .unwrap();
+        assert_eq!(parsed.pubkey.compress(), signed.pubkey.compress());
+        assert_eq!(
+            parsed.signature.r_b8.compress(),
+            signed.signature.r_b8.compress()
+        );
+        assert_eq!(parsed.signature.s, signed.signature.s);
+
+        let point = serde_json::json!({
+            "x": signed.pubkey.x.to_decimal_string(),
+            "y": signed.pubkey.y.to_decimal_string(),
+        });
+        let pubkey = <Pubkey as PubkeyHex>::deserialize(point).unwrap();
+        assert_eq!((pubkey.x, pubkey.y), (signed.pubkey.x, signed.pubkey.y));
+
+        let off_curve = serde_json::json!({
+            "x": signed.pubkey.y.to_decimal_string(),
+            "y": signed.pubkey.x.to_decimal_string(),
+        });
+        assert!(<Pubkey as PubkeyHex>::deserialize(off_curve).is_err());
+
+        // coordinates outside the field are errors, not panics
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        for x in ["-1", modulus] {
+            let point = serde_json::json!({
+                "x": x,
+                "y": signed.pubkey.y.to_decimal_string(),
+            });
+            assert!(<Pubkey as PubkeyHex>::deserialize(point).is_err());
+        }
+
+        // (0, -1) is on the curve but of order 2, rejected in both forms
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        let low_order = Pubkey {
+            x: Fr::zero(),
+            y: minus_one,
+        };
+        let hex = serde_json::json!(low_order.to_hex_string());
+        assert!(<Pubkey as PubkeyHex>::deserialize(hex).is_err());
+        let point = serde_json::json!({
+            "x": "0",
+            "y": minus_one.to_decimal_string(),
+        });
+        assert!(<Pubkey as PubkeyHex>::deserialize(point).is_err());
+
+        let suborder = SUBORDER.to_string();
+        for s in ["-1", suborder.as_str()] {
+            let signature = serde_json::json!({
+                "r_b8": {
+                    "x": signed.signature.r_b8.x.to_decimal_string(),
+                    "y": signed.signature.r_b8.y.to_decimal_string(),
+                },
+                "s": s,
+            });
+            assert!(<Signature as SignatureHex>::deserialize(signature).is_err());
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use super::{BigInt, Floats, Fr, FrExt, PackError, Significand};
+use crate::num_traits::{Signed, ToPrimitive, Zero};
+
+type Result<T, E = PackError> = std::result::Result<T, E>;
+
+/// Write fields at arbitrary bit widths, most significant bit first, as rollup public
+/// data lays them out.
+///
+/// ```
+/// use fluidex_common::types::{BitReader, BitWriter, Float40};
+///
+/// let amount = Float40 { exponent: 3, significand: 5 };
+/// let mut writer = BitWriter::new();
+/// writer.write_uint(7u32, 20)?.write_floats(amount, 40)?.pad_to_byte();
+/// let data = writer.to_bytes();
+///
+/// let mut reader = BitReader::new(&data);
+/// assert_eq!(reader.read_u64(20)?, 7);
+/// let read: Float40 = reader.read_floats(40)?;
+/// assert_eq!(read, amount);
+/// reader.skip_to_byte()?;
+/// # Ok::<(), fluidex_common::types::PackError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of bits written so far.
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn write_bool(&mut self, bit: bool) -> &mut Self {
+        self.bits.push(bit);
+        self
+    }
+
+    /// Write `value` in `width` bits, failing if it is negative or needs more bits.
+    pub fn write_uint<T: Into<BigInt>>(&mut self, value: T, width: usize) -> Result<&mut Self> {
+        let value = value.into();
+        if value.is_negative() || value.bits() > width as u64 {
+            return Err(PackError::Overflow(value, width));
+        }
+        let (_, bytes) = value.to_bytes_le();
+        self.bits.extend(
+            (0..width)
+                .rev()
+                .map(|i| (bytes.get(i / 8).copied().unwrap_or(0) >> (i % 8)) & 1 == 1),
+        );
+        Ok(self)
+    }
+
+    /// Write the encoded integer of `f` in `width` bits, usually
+    /// [`Floats::ENCODED_BYTES`] bytes.
+    pub fn write_floats<T: Significand, const NBITS: usize, const LAYOUT: u8>(
+        &mut self,
+        f: Floats<T, NBITS, LAYOUT>,
+        width: usize,
+    ) -> Result<&mut Self> {
+        let encoded = f.to_encoded_int()?;
+        self.write_uint(encoded, width)
+    }
+
+    /// Write each element in `width` bits, writing nothing if any of them needs more.
+    pub fn write_frs(&mut self, frs: &[Fr], width: usize) -> Result<&mut Self> {
+        let bits = frs
+            .iter()
+            .map(|fr| fr.to_bits_be(width))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        self.bits.extend(bits.into_iter().flatten());
+        Ok(self)
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.bits.extend(
+            bytes
+                .iter()
+                .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1)),
+        );
+        self
+    }
+
+    /// Append zeros until the length is a multiple of `multiple` bits, failing on 0.
+    pub fn pad_to(&mut self, multiple: usize) -> Result<&mut Self> {
+        if multiple == 0 {
+            return Err(PackError::ZeroWidth);
+        }
+        let rem = self.bits.len() % multiple;
+        if rem != 0 {
+            self.bits.resize(self.bits.len() + multiple - rem, false);
+        }
+        Ok(self)
+    }
+
+    pub fn pad_to_byte(&mut self) -> &mut Self {
+        self.pad_to(8).expect("8 is not zero")
+    }
+
+    pub fn as_bits(&self) -> &[bool] {
+        &self.bits
+    }
+
+    /// The written bits as bytes, zero padded at the end of the last byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, bit)| byte | ((*bit as u8) << (7 - i)))
+            })
+            .collect()
+    }
+
+    /// The written bits as field elements of `width` bits each, the last one zero padded
+    /// at the end.
+    pub fn to_frs(&self, width: usize) -> Result<Vec<Fr>> {
+        if width == 0 {
+            return Err(PackError::ZeroWidth);
+        }
+        self.bits
+            .chunks(width)
+            .map(|chunk| {
+                let mut bits = chunk.to_vec();
+                bits.resize(width, false);
+                Ok(Fr::from_bits_be(&bits)?)
+            })
+            .collect()
+    }
+}
+
+/// Read fields written by [`BitWriter`] back from bytes.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Number of bits read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    fn bit(&self, pos: usize) -> bool {
+        (self.data[pos / 8] >> (7 - pos % 8)) & 1 == 1
+    }
+
+    /// Run `read`, moving back to the current position if it fails.
+    fn rewind_on_error<R>(&mut self, read: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+        let pos = self.pos;
+        let result = read(self);
+        if result.is_err() {
+            self.pos = pos;
+        }
+        result
+    }
+
+    fn take(&mut self, width: usize) -> Result<impl Iterator<Item = bool> + '_> {
+        if width > self.remaining() {
+            return Err(PackError::Eof(width, self.remaining()));
+        }
+        let start = self.pos;
+        self.pos += width;
+        Ok((start..start + width).map(move |pos| self.bit(pos)))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.take(1)?.all(|bit| bit))
+    }
+
+    pub fn read_uint(&mut self, width: usize) -> Result<BigInt> {
+        Ok(self
+            .take(width)?
+            .fold(BigInt::zero(), |value, bit| (value << 1) + bit as u8))
+    }
+
+    /// Like [`BitReader::read_uint`], for fields of at most 64 bits.
+    pub fn read_u64(&mut self, width: usize) -> Result<u64> {
+        self.rewind_on_error(|reader| {
+            let value = reader.read_uint(width)?;
+            value.to_u64().ok_or(PackError::Overflow(value, 64))
+        })
+    }
+
+    /// Read a float written by [`BitWriter::write_floats`] in `width` bits.
+    pub fn read_floats<T: Significand, const NBITS: usize, const LAYOUT: u8>(
+        &mut self,
+        width: usize,
+    ) -> Result<Floats<T, NBITS, LAYOUT>> {
+        self.rewind_on_error(|reader| {
+            let encoded = reader.read_uint(width)?;
+            Ok(Floats::from_encoded_bigint(encoded)?)
+        })
+    }
+
+    /// Read `count` field elements of `width` bits each, reading nothing if any of them
+    /// is not below the modulus.
+    pub fn read_frs(&mut self, count: usize, width: usize) -> Result<Vec<Fr>> {
+        self.rewind_on_error(|reader| {
+            (0..count)
+                .map(|_| {
+                    let bits: Vec<bool> = reader.take(width)?.collect();
+                    Ok(Fr::from_bits_be(&bits)?)
+                })
+                .collect()
+        })
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let bits: Vec<bool> = self.take(len * 8)?.collect();
+        Ok(bits
+            .chunks(8)
+            .map(|chunk| chunk.iter().fold(0u8, |byte, bit| (byte << 1) | *bit as u8))
+            .collect())
+    }
+
+    /// Skip to the next multiple of `multiple` bits, failing on 0 or if the skipped
+    /// padding is not all zeros.
+    pub fn skip_to(&mut self, multiple: usize) -> Result<()> {
+        if multiple == 0 {
+            return Err(PackError::ZeroWidth);
+        }
+        let rem = self.pos % multiple;
+        if rem == 0 {
+            return Ok(());
+        }
+        self.rewind_on_error(|reader| {
+            let start = reader.pos;
+            if reader.take(multiple - rem)?.any(|bit| bit) {
+                return Err(PackError::Padding(start));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn skip_to_byte(&mut self) -> Result<()> {
+        self.skip_to(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Float40, FloatsError};
+
+    #[test]
+    fn test_bits_round_trip() {
+        let amount = Float40 {
+            exponent: 2,
+            significand: -7,
+        };
+        let frs = [Fr::from_u32(3), Fr::from_u32(0x1ff)];
+        let mut writer = BitWriter::new();
+        writer
+            .write_uint(5u32, 3)
+            .unwrap()
+            .write_bool(true)
+            .write_floats(amount, 40)
+            .unwrap()
+            .write_frs(&frs, 10)
+            .unwrap()
+            .write_bytes(&[0xab, 0xcd])
+            .pad_to_byte();
+        assert_eq!(writer.len(), 80);
+        assert_eq!(writer.to_bytes()[0] >> 4, 0b1011);
+
+        let data = writer.to_bytes();
+        let mut reader = BitReader::new(&data);
+        assert_eq!(reader.read_u64(3).unwrap(), 5);
+        assert!(reader.read_bool().unwrap());
+        let read: Float40 = reader.read_floats(40).unwrap();
+        assert_eq!(read, amount);
+        assert_eq!(reader.read_frs(2, 10).unwrap(), frs.to_vec());
+        assert_eq!(reader.read_bytes(2).unwrap(), vec![0xab, 0xcd]);
+        reader.skip_to_byte().unwrap();
+        assert_eq!(reader.remaining(), 0);
+        assert!(matches!(reader.read_bool(), Err(PackError::Eof(1, 0))));
+    }
+
+    #[test]
+    fn test_bits_errors() {
+        let mut writer = BitWriter::new();
+        assert!(writer.write_uint(8u32, 3).is_err());
+        assert!(writer.write_uint(-1i32, 8).is_err());
+        assert!(writer.write_frs(&[Fr::from_u32(4)], 2).is_err());
+        // the valid elements before a failing one are not written either
+        assert!(writer
+            .write_frs(&[Fr::from_u32(1), Fr::from_u32(4)], 2)
+            .is_err());
+        assert!(writer.is_empty());
+        assert!(matches!(writer.pad_to(0), Err(PackError::ZeroWidth)));
+        assert!(matches!(writer.to_frs(0), Err(PackError::ZeroWidth)));
+
+        let mut reader = BitReader::new(&[0b1000_0001]);
+        reader.read_bool().unwrap();
+        assert!(matches!(reader.skip_to_byte(), Err(PackError::Padding(1))));
+        assert_eq!(reader.position(), 1);
+        assert!(matches!(reader.skip_to(0), Err(PackError::ZeroWidth)));
+
+        let mut writer = BitWriter::new();
+        writer.write_uint(1u32, 4).unwrap().pad_to(16).unwrap();
+        assert_eq!(
+            writer.to_frs(8).unwrap(),
+            vec![Fr::from_u32(0x10), Fr::from_u32(0)]
+        );
+    }
+
+    #[test]
+    fn test_bits_failed_reads_keep_position() {
+        // a valid element followed by one of 256 set bits, above the modulus
+        let mut data = vec![0u8; 32];
+        data[31] = 1;
+        data.extend_from_slice(&[0xff; 32]);
+        let mut reader = BitReader::new(&data);
+        assert!(reader.read_frs(2, 256).is_err());
+        assert_eq!(reader.position(), 0);
+        assert_eq!(reader.read_frs(1, 256).unwrap(), vec![Fr::from_u32(1)]);
+        assert_eq!(reader.position(), 256);
+
+        assert!(matches!(
+            reader.read_u64(80),
+            Err(PackError::Overflow(_, 64))
+        ));
+        assert_eq!(reader.position(), 256);
+        assert!(matches!(
+            reader.read_bytes(33),
+            Err(PackError::Eof(264, 256))
+        ));
+        assert_eq!(reader.position(), 256);
+
+        // an exponent of 32 needs more than the 5 bits of Float40
+        let mut writer = BitWriter::new();
+        writer.write_uint(32u64 << 35, 48).unwrap();
+        let data = writer.to_bytes();
+        let mut reader = BitReader::new(&data);
+        let read: Result<Float40> = reader.read_floats(48);
+        assert!(matches!(
+            read,
+            Err(PackError::Floats(FloatsError::ExponentTooBig))
+        ));
+        assert_eq!(reader.position(), 0);
+
+        let mut writer = BitWriter::new();
+        let amount = Float40 {
+            exponent: 31,
+            significand: 1,
+        };
+        writer.write_floats(amount, 48).unwrap();
+        assert_eq!(writer.len(), 48);
+        let data = writer.to_bytes();
+        let read: Float40 = BitReader::new(&data).read_floats(48).unwrap();
+        assert_eq!(read, amount);
+        assert!(BitWriter::new().write_floats(amount, 39).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! `sqlx` support for [`Floats`], including `Float864`, stored as their packed bytes in a
+//! `bytea` column.
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{Decode, Encode, Type};
+
+use super::{Floats, Significand};
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Type<Postgres>
+    for Floats<T, NBITS, LAYOUT>
+{
+    fn type_info() -> PgTypeInfo {
+        <Vec<u8> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Vec<u8> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+/// The `Encode` of sqlx 0.5 has no way to report an error, so this panics like
+/// [`Floats::encode`] on a value [`Floats::try_encode`] rejects. Bind
+/// `f.try_encode()?` instead where the value is not known to be encodable.
+impl<'q, T: Significand, const NBITS: usize, const LAYOUT: u8> Encode<'q, Postgres>
+    for Floats<T, NBITS, LAYOUT>
+{
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <Vec<u8> as Encode<'q, Postgres>>::encode(self.encode(), buf)
+    }
+}
+
+impl<'r, T: Significand, const NBITS: usize, const LAYOUT: u8> Decode<'r, Postgres>
+    for Floats<T, NBITS, LAYOUT>
+{
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <&[u8] as Decode<'r, Postgres>>::decode(value)?;
+        Ok(Self::decode(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Float40, Float864, FloatsError};
+
+    #[test]
+    fn test_floats_bytea() {
+        let amount = Float864 {
+            exponent: 13,
+            significand: 123456,
+        };
+        let mut buf = PgArgumentBuffer::default();
+        assert!(matches!(
+            Encode::<Postgres>::encode_by_ref(&amount, &mut buf),
+            IsNull::No
+        ));
+        assert_eq!(&buf[..], &amount.encode()[..]);
+        assert_eq!(Float864::decode(&buf).unwrap(), amount);
+
+        let fee = Float40 {
+            exponent: 3,
+            significand: -5,
+        };
+        let mut buf = PgArgumentBuffer::default();
+        Encode::<Postgres>::encode_by_ref(&fee, &mut buf);
+        assert_eq!(Float40::decode(&buf).unwrap(), fee);
+        assert!(matches!(
+            Float40::decode(&buf[1..]),
+            Err(FloatsError::InvalidLength(5, 4))
+        ));
+
+        let out_of_range = Float40 {
+            exponent: 32,
+            significand: 1,
+        };
+        assert!(matches!(
+            out_of_range.try_encode(),
+            Err(FloatsError::ExponentTooBig)
+        ));
+    }
+}
@@ -1,27 +1,253 @@
-use super::{Decimal, Float864, FrExt};
-use crate::num_traits::{Pow, ToPrimitive};
+use super::{BigInt, Decimal, Float864, Floats, FloatsError, FrExt, FrExtError, Significand};
+use crate::num_traits::{Signed, ToPrimitive, Zero};
 use crate::Fr;
 
+/// How to round a value that is not representable in the target precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Toward zero
+    Down,
+    /// Away from zero
+    Up,
+    /// To the nearest value, ties to the even one
+    HalfEven,
+    /// Fail instead of rounding
+    Exact,
+}
+
+impl RoundingMode {
+    /// Divide `n` by the positive `d`, `None` if the result is inexact in [`RoundingMode::Exact`].
+    pub fn div(self, n: &BigInt, d: &BigInt) -> Option<BigInt> {
+        let q = n / d;
+        let r = n % d;
+        if r.is_zero() {
+            return Some(q);
+        }
+        let away = &q + if n.is_negative() { -1 } else { 1 };
+        match self {
+            RoundingMode::Down => Some(q),
+            RoundingMode::Up => Some(away),
+            RoundingMode::HalfEven => {
+                let double = r.abs() * 2;
+                if double > *d || (double == *d && !(&q % 2u32).is_zero()) {
+                    Some(away)
+                } else {
+                    Some(q)
+                }
+            }
+            RoundingMode::Exact => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecimalExtError {
+    #[error("negative value {0}")]
+    Negative(Decimal),
+    #[error("{0} has more than {1} decimal places")]
+    Fractional(Decimal, u32),
+    #[error("{0} with precision {1} overflows {2}")]
+    Overflow(Decimal, u32, &'static str),
+    #[error(transparent)]
+    Fr(#[from] FrExtError),
+    #[error(transparent)]
+    Floats(#[from] FloatsError),
+}
+
+type Result<T, E = DecimalExtError> = std::result::Result<T, E>;
+
+/// The infallible conversions are shorthands for the `try_` ones.
 pub trait DecimalExt {
+    /// [`DecimalExt::try_to_u64`] rounding down.
+    ///
+    /// # Panics
+    ///
+    /// If the value is negative or overflows u64.
     fn to_u64(&self, prec: u32) -> u64;
+    /// [`DecimalExt::try_to_fr`] rounding down.
+    ///
+    /// # Panics
+    ///
+    /// If the value is negative or overflows the field.
     fn to_fr(&self, prec: u32) -> Fr;
+    /// [`DecimalExt::try_to_floats`] without rounding.
+    ///
+    /// # Panics
+    ///
+    /// If the value is negative or not exactly representable as [`Float864`].
     fn to_amount(&self, prec: u32) -> Float864;
+    /// The value times `10^prec` as an integer, rounded by `rounding`.
+    fn try_to_bigint(&self, prec: u32, rounding: RoundingMode) -> Result<BigInt>;
+    /// Like [`DecimalExt::try_to_bigint`], rejecting negative values and values beyond u64.
+    fn try_to_u64(&self, prec: u32, rounding: RoundingMode) -> Result<u64>;
+    /// Like [`DecimalExt::try_to_bigint`], rejecting negative values and values beyond u128.
+    fn try_to_u128(&self, prec: u32, rounding: RoundingMode) -> Result<u128>;
+    /// Like [`DecimalExt::try_to_bigint`], rejecting negative values and values beyond the field.
+    fn try_to_fr(&self, prec: u32, rounding: RoundingMode) -> Result<Fr>;
+    /// The value times `10^prec` as any [`Floats`] alias, rounded by `rounding`.
+    fn try_to_floats<T: Significand, const NBITS: usize, const LAYOUT: u8>(
+        &self,
+        prec: u32,
+        rounding: RoundingMode,
+    ) -> Result<Floats<T, NBITS, LAYOUT>>;
 }
 
 impl DecimalExt for Decimal {
     fn to_u64(&self, prec: u32) -> u64 {
-        let prec_mul = Decimal::new(10, 0).pow(prec as u64);
-        let adjusted = self * prec_mul;
-        ToPrimitive::to_u64(&adjusted.floor()).unwrap()
+        self.try_to_u64(prec, RoundingMode::Down).unwrap()
     }
 
     fn to_fr(&self, prec: u32) -> Fr {
-        // TODO: is u64 enough?
-        Fr::from_u64(DecimalExt::to_u64(self, prec))
-        // Float864::from_decimal(num, prec).unwrap().to_fr()
+        self.try_to_fr(prec, RoundingMode::Down).unwrap()
     }
 
     fn to_amount(&self, prec: u32) -> Float864 {
-        Float864::from_decimal(self, prec).unwrap()
+        self.try_to_floats(prec, RoundingMode::Exact).unwrap()
+    }
+
+    fn try_to_bigint(&self, prec: u32, rounding: RoundingMode) -> Result<BigInt> {
+        // self is mantissa / 10^scale, so the result is mantissa * 10^(prec - scale)
+        let mantissa = BigInt::from(self.mantissa());
+        let scale = self.scale();
+        if scale <= prec {
+            Ok(mantissa * BigInt::from(10).pow(prec - scale))
+        } else {
+            rounding
+                .div(&mantissa, &BigInt::from(10).pow(scale - prec))
+                .ok_or(DecimalExtError::Fractional(*self, prec))
+        }
+    }
+
+    fn try_to_u64(&self, prec: u32, rounding: RoundingMode) -> Result<u64> {
+        if self.is_sign_negative() && !self.is_zero() {
+            return Err(DecimalExtError::Negative(*self));
+        }
+        self.try_to_bigint(prec, rounding)?
+            .to_u64()
+            .ok_or(DecimalExtError::Overflow(*self, prec, "u64"))
+    }
+
+    fn try_to_u128(&self, prec: u32, rounding: RoundingMode) -> Result<u128> {
+        if self.is_sign_negative() && !self.is_zero() {
+            return Err(DecimalExtError::Negative(*self));
+        }
+        self.try_to_bigint(prec, rounding)?
+            .to_u128()
+            .ok_or(DecimalExtError::Overflow(*self, prec, "u128"))
+    }
+
+    fn try_to_fr(&self, prec: u32, rounding: RoundingMode) -> Result<Fr> {
+        if self.is_sign_negative() && !self.is_zero() {
+            return Err(DecimalExtError::Negative(*self));
+        }
+        let i = self.try_to_bigint(prec, rounding)?;
+        Fr::try_from_bigint(i).map_err(|_| DecimalExtError::Overflow(*self, prec, "Fr"))
+    }
+
+    fn try_to_floats<T: Significand, const NBITS: usize, const LAYOUT: u8>(
+        &self,
+        prec: u32,
+        rounding: RoundingMode,
+    ) -> Result<Floats<T, NBITS, LAYOUT>> {
+        let (f, _) = Floats::from_decimal_rounded(self, prec, rounding)?;
+        Ok(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_rounding() {
+        let cases = [
+            (25, 10, [2, 3, 2]),
+            (35, 10, [3, 4, 4]),
+            (36, 10, [3, 4, 4]),
+            (-25, 10, [-2, -3, -2]),
+            (-26, 10, [-2, -3, -3]),
+        ];
+        for (n, d, [down, up, half_even]) in cases {
+            let (n, d) = (BigInt::from(n), BigInt::from(d));
+            assert_eq!(RoundingMode::Down.div(&n, &d), Some(BigInt::from(down)));
+            assert_eq!(RoundingMode::Up.div(&n, &d), Some(BigInt::from(up)));
+            assert_eq!(
+                RoundingMode::HalfEven.div(&n, &d),
+                Some(BigInt::from(half_even))
+            );
+            assert_eq!(RoundingMode::Exact.div(&n, &d), None);
+        }
+        assert_eq!(
+            RoundingMode::Exact.div(&BigInt::from(30), &BigInt::from(10)),
+            Some(BigInt::from(3))
+        );
+    }
+
+    #[test]
+    fn test_wide_conversions() {
+        // 100 ETH overflows u64 with 18 decimals
+        let d = Decimal::from_str("100.123456789012345678").unwrap();
+        assert_eq!(
+            d.try_to_u128(18, RoundingMode::Exact).unwrap(),
+            100123456789012345678u128
+        );
+        assert_eq!(
+            d.try_to_fr(18, RoundingMode::Exact).unwrap(),
+            Fr::from_u128(100123456789012345678u128)
+        );
+        assert_eq!(d.try_to_u128(2, RoundingMode::Up).unwrap(), 10013u128);
+        assert!(matches!(
+            d.try_to_u128(2, RoundingMode::Exact),
+            Err(DecimalExtError::Fractional(_, 2))
+        ));
+
+        let neg = Decimal::from_str("-1.5").unwrap();
+        assert_eq!(
+            neg.try_to_bigint(0, RoundingMode::HalfEven).unwrap(),
+            BigInt::from(-2)
+        );
+        assert!(matches!(
+            neg.try_to_u128(0, RoundingMode::Down),
+            Err(DecimalExtError::Negative(_))
+        ));
+        assert!(matches!(
+            neg.try_to_fr(0, RoundingMode::Down),
+            Err(DecimalExtError::Negative(_))
+        ));
+
+        assert_eq!(d.to_u64(3), 100123u64);
+        assert_eq!(d.try_to_u64(3, RoundingMode::Up).unwrap(), 100124u64);
+        assert!(matches!(
+            d.try_to_u64(18, RoundingMode::Down),
+            Err(DecimalExtError::Overflow(_, 18, "u64"))
+        ));
+        assert!(matches!(
+            neg.try_to_u64(0, RoundingMode::Down),
+            Err(DecimalExtError::Negative(_))
+        ));
+
+        let big = Decimal::from_str("79228162514264337593543950335").unwrap();
+        assert!(matches!(
+            big.try_to_u128(18, RoundingMode::Down),
+            Err(DecimalExtError::Overflow(_, 18, "u128"))
+        ));
+        assert!(big.try_to_fr(18, RoundingMode::Down).is_ok());
+        assert!(matches!(
+            big.try_to_fr(60, RoundingMode::Down),
+            Err(DecimalExtError::Overflow(_, 60, "Fr"))
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_to_u64_negative() {
+        Decimal::from_str("-0.5").unwrap().to_u64(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_to_u64_overflow() {
+        Decimal::from_str("100.5").unwrap().to_u64(18);
     }
 }
@@ -0,0 +1,87 @@
+use ethers::core::types::{H256, U256};
+
+use super::{reduce_repr, Fr, FrExt, FrExtError};
+use crate::ff::PrimeField;
+
+type Result<T, E = FrExtError> = std::result::Result<T, E>;
+
+/// Conversions between [`Fr`] and the ethers [`U256`]/[`H256`] types.
+///
+/// The plain constructors reject values that are not smaller than the field modulus,
+/// the `_reduced` ones reduce them modulo the field modulus instead.
+pub trait FrEthExt: Sized {
+    fn from_u256(x: U256) -> Result<Self>;
+    fn from_u256_reduced(x: U256) -> Self;
+    fn to_u256(&self) -> U256;
+    fn from_h256(x: H256) -> Result<Self>;
+    fn from_h256_reduced(x: H256) -> Self;
+    fn to_h256(&self) -> H256;
+}
+
+impl FrEthExt for Fr {
+    fn from_u256(x: U256) -> Result<Self> {
+        // both U256 and FrRepr are little-endian u64 limbs
+        let mut repr = poseidon_rs::FrRepr::default();
+        repr.as_mut().copy_from_slice(&x.0);
+        Ok(Fr::from_repr(repr)?)
+    }
+
+    fn from_u256_reduced(x: U256) -> Self {
+        let mut repr = poseidon_rs::FrRepr::default();
+        repr.as_mut().copy_from_slice(&x.0);
+        reduce_repr(repr)
+    }
+
+    fn to_u256(&self) -> U256 {
+        let mut limbs = [0u64; 4];
+        limbs.copy_from_slice(self.into_repr().as_ref());
+        U256(limbs)
+    }
+
+    fn from_h256(x: H256) -> Result<Self> {
+        Self::from_be_bytes(x.to_fixed_bytes())
+    }
+
+    fn from_h256_reduced(x: H256) -> Self {
+        Self::from_be_bytes_reduced(x.to_fixed_bytes())
+    }
+
+    fn to_h256(&self) -> H256 {
+        H256::from(self.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u256() {
+        let fr = Fr::from_u128(u128::MAX);
+        let x = fr.to_u256();
+        assert_eq!(x, U256::from(u128::MAX));
+        assert_eq!(Fr::from_u256(x).unwrap(), fr);
+
+        assert!(Fr::from_u256(U256::MAX).is_err());
+        assert_eq!(
+            Fr::from_u256_reduced(U256::MAX),
+            Fr::from_be_bytes_reduced([0xff; 32])
+        );
+    }
+
+    #[test]
+    fn test_h256() {
+        let fr = Fr::from_u32(0x0c45);
+        let h = fr.to_h256();
+        assert_eq!(&h.as_bytes()[30..], &[0x0c, 0x45]);
+        assert_eq!(Fr::from_h256(h).unwrap(), fr);
+        assert_eq!(Fr::from_h256_reduced(h), fr);
+        assert_eq!(U256::from_big_endian(h.as_bytes()), fr.to_u256());
+
+        assert!(Fr::from_h256(H256::repeat_byte(0xff)).is_err());
+        assert_eq!(
+            Fr::from_h256_reduced(H256::repeat_byte(0xff)),
+            Fr::from_u256_reduced(U256::MAX)
+        );
+    }
+}
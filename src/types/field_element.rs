@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::ff::{Field, PrimeField};
+use crate::types::{Fr, FrExt};
+
+/// A [`Fr`] wrapper with arithmetic operators, ordered by its canonical integer value.
+///
+/// It converts to and from [`Fr`] without any cost, and can be (de)serialized by the
+/// `FrBytes` and `FrStr` adapters in [`crate::serde`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FieldElement(pub Fr);
+
+impl FieldElement {
+    pub fn zero() -> Self {
+        Self(Fr::zero())
+    }
+
+    pub fn one() -> Self {
+        Self(Fr::one())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn into_inner(self) -> Fr {
+        self.0
+    }
+
+    pub fn square(&self) -> Self {
+        let mut r = self.0;
+        r.square();
+        Self(r)
+    }
+
+    pub fn pow(&self, exp: u64) -> Self {
+        Self(self.0.pow([exp]))
+    }
+
+    /// Multiplicative inverse, `None` for zero.
+    pub fn inverse(&self) -> Option<Self> {
+        self.0.inverse().map(Self)
+    }
+}
+
+impl From<Fr> for FieldElement {
+    fn from(fr: Fr) -> Self {
+        Self(fr)
+    }
+}
+
+impl From<FieldElement> for Fr {
+    fn from(fe: FieldElement) -> Self {
+        fe.0
+    }
+}
+
+impl AsRef<Fr> for FieldElement {
+    fn as_ref(&self) -> &Fr {
+        &self.0
+    }
+}
+
+macro_rules! impl_from_unsigned {
+    ($ctor:ident: $($t:ty),*) => {
+        $(
+            impl From<$t> for FieldElement {
+                fn from(x: $t) -> Self {
+                    Self(Fr::$ctor(x.into()))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_signed {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for FieldElement {
+                fn from(x: $t) -> Self {
+                    let r = Self::from(x.unsigned_abs());
+                    if x < 0 {
+                        -r
+                    } else {
+                        r
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_unsigned!(from_u64: u8, u16, u32, u64);
+impl_from_unsigned!(from_u128: u128);
+
+impl_from_signed!(i8, i16, i32, i64, i128);
+
+impl From<bool> for FieldElement {
+    fn from(b: bool) -> Self {
+        if b {
+            Self::one()
+        } else {
+            Self::zero()
+        }
+    }
+}
+
+impl Add for FieldElement {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for FieldElement {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0.add_assign(&rhs.0);
+    }
+}
+
+impl Sub for FieldElement {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+impl SubAssign for FieldElement {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0.sub_assign(&rhs.0);
+    }
+}
+
+impl Mul for FieldElement {
+    type Output = Self;
+
+    fn mul(mut self, rhs: Self) -> Self {
+        self *= rhs;
+        self
+    }
+}
+
+impl MulAssign for FieldElement {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0.mul_assign(&rhs.0);
+    }
+}
+
+impl Div for FieldElement {
+    type Output = Self;
+
+    /// ## panics
+    /// When `rhs` is zero.
+    fn div(mut self, rhs: Self) -> Self {
+        self /= rhs;
+        self
+    }
+}
+
+impl DivAssign for FieldElement {
+    /// ## panics
+    /// When `rhs` is zero.
+    fn div_assign(&mut self, rhs: Self) {
+        let inv = rhs.inverse().expect("division by zero");
+        *self *= inv;
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.0.negate();
+        self
+    }
+}
+
+impl Sum for FieldElement {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl Product for FieldElement {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), Mul::mul)
+    }
+}
+
+impl PartialOrd for FieldElement {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FieldElement {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.into_repr().cmp(&other.0.into_repr())
+    }
+}
+
+impl Hash for FieldElement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_repr().as_ref().hash(state);
+    }
+}
+
+impl Default for FieldElement {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl fmt::Display for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_decimal_string())
+    }
+}
+
+impl fmt::Debug for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FieldElement({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = FieldElement::from(6u32);
+        let b = FieldElement::from(4u32);
+        assert_eq!(a + b, FieldElement::from(10u32));
+        assert_eq!(a - b, FieldElement::from(2u32));
+        assert_eq!(b - a, FieldElement::from(-2i32));
+        assert_eq!(a * b, FieldElement::from(24u32));
+        assert_eq!(a / b * b, a);
+        assert_eq!(-a + a, FieldElement::zero());
+        assert_eq!(b.pow(3), FieldElement::from(64u32));
+        assert_eq!(FieldElement::zero().inverse(), None);
+        assert_eq!(b.inverse().unwrap() * b, FieldElement::one());
+
+        let mut c = a;
+        c += b;
+        c *= b;
+        c -= a;
+        c /= b;
+        assert_eq!(c * b, FieldElement::from(34u32));
+
+        let v = vec![a, b, FieldElement::from(true)];
+        assert_eq!(
+            v.iter().copied().sum::<FieldElement>(),
+            FieldElement::from(11u8)
+        );
+        assert_eq!(
+            v.into_iter().product::<FieldElement>(),
+            FieldElement::from(24u8)
+        );
+    }
+
+    #[test]
+    fn test_ord_and_display() {
+        let minus_one = FieldElement::from(-1i64);
+        assert!(minus_one > FieldElement::from(u128::MAX));
+        assert!(FieldElement::from(1u8) < FieldElement::from(2u8));
+        assert_eq!(
+            minus_one.to_string(),
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+        );
+        let fr: Fr = FieldElement::from(7u64).into();
+        assert_eq!(fr, Fr::from_u64(7));
+        assert_eq!(FieldElement::from(fr).to_string(), "7");
+    }
+}
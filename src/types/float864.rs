@@ -1,102 +1,39 @@
-use std::convert::TryInto;
-
-use super::{BigInt, Decimal};
-use crate::num_traits::Pow;
-use crate::types::{Fr, FrExt};
+use super::floats::layout;
+use super::{Floats, FloatsError};
 
 /// a float representation with 1 byte exponent and 8 bytes significand
-#[derive(Debug, Clone, Copy)]
-pub struct Float864 {
-    pub exponent: u8,
-    // 5 bytes seems enough?
-    pub significand: u64,
-}
-
-#[derive(Debug, thiserror::Error)]
-pub enum Float864Error {
-    #[error("decimal precision error {0} {1}")]
-    Precision(Decimal, u32),
-    #[error("invalid precision {0} {1} {2}")]
-    InvalidPrecision(Decimal, u32, Decimal),
-    #[error(transparent)]
-    TryFromSlice(#[from] std::array::TryFromSliceError),
-    #[error(transparent)]
-    ParseInt(#[from] std::num::ParseIntError),
-}
-
-type Result<T, E = Float864Error> = std::result::Result<T, E>;
+///
+/// The encoding is the exponent byte followed by the big-endian `u64` significand.
+/// The significand is kept within 62 bits, see [`Floats::MAX_SIGNIFICAND`], as the older
+/// conversions did, and encoding or decoding a wider one fails. Data written by the older
+/// codec, which took any `u64`, reads back with [`Float864::decode_legacy`].
+pub type Float864 = Floats<u64, 62, { layout::BYTE_ALIGNED }>;
 
 impl Float864 {
-    pub fn to_bigint(self) -> BigInt {
-        let s = BigInt::from(self.significand);
-        s * BigInt::from(10).pow(self.exponent)
-    }
-
-    pub fn to_fr(self) -> Fr {
-        Fr::from_bigint(self.to_bigint())
-    }
-
-    pub fn encode(self) -> Vec<u8> {
-        let mut result = self.exponent.to_be_bytes().to_vec();
-        result.append(&mut self.significand.to_be_bytes().to_vec());
-        result
-    }
-
-    pub fn decode(data: &[u8]) -> Result<Self> {
-        let exponent = u8::from_be_bytes(data[0..1].try_into()?);
-        let significand = u64::from_be_bytes(data[1..9].try_into()?);
-        Ok(Self {
-            exponent,
-            significand,
-        })
-    }
-
-    pub fn to_decimal(self, prec: u32) -> Decimal {
-        // for example, (significand:1, exponent:17) means 10**17, when prec is 18,
-        // it is 0.1 (ETH)
-        Decimal::new(self.significand as i64, 0) * Decimal::new(10, 0).pow(self.exponent as u64)
-            / Decimal::new(10, 0).pow(prec as u64)
-    }
-
-    pub fn from_decimal(d: &Decimal, prec: u32) -> Result<Self> {
-        // if d is "0.1" and prec is 18, result is (significand:1, exponent:17)
-        if d.is_zero() {
-            return Ok(Self {
-                exponent: 0,
-                significand: 0,
-            });
-        }
-        let ten = Decimal::new(10, 0);
-        let exp = ten.pow(prec as u64);
-        let mut n = d * exp;
-        if n != n.floor() {
-            return Err(Float864Error::Precision(*d, prec));
+    /// Read the first [`Floats::ENCODED_BYTES`] bytes as the older codec did, ignoring
+    /// any trailing bytes and taking the whole `u64` range for the significand.
+    ///
+    /// A significand beyond 62 bits converts with [`Floats::to_bigint`] and
+    /// [`Floats::to_decimal`], but fails to encode again.
+    pub fn decode_legacy(data: &[u8]) -> Result<Self, FloatsError> {
+        if data.len() < Self::ENCODED_BYTES {
+            return Err(FloatsError::InvalidLength(Self::ENCODED_BYTES, data.len()));
         }
-        let mut exponent = 0;
-        loop {
-            let next = n / ten;
-            if next == next.floor() {
-                exponent += 1;
-                n = next;
-            } else {
-                break;
-            }
-        }
-        if n > Decimal::new((u64::MAX / 4) as i64, 0) {
-            return Err(Float864Error::InvalidPrecision(*d, prec, n));
-        }
-        // TODO: a better way...
-        let significand: u64 = n.floor().to_string().parse::<u64>()?;
-        Ok(Float864 {
-            exponent,
-            significand,
+        Ok(Self {
+            exponent: data[0],
+            significand: u64::from_be_bytes(data[1..Self::ENCODED_BYTES].try_into()?),
         })
     }
 }
 
+/// Errors of [`Float864`], the same as [`Floats`].
+pub type Float864Error = FloatsError;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::num_traits::Pow;
+    use crate::types::{BigInt, Decimal, RoundingMode};
     use std::str::FromStr;
 
     #[test]
@@ -106,10 +43,147 @@ mod tests {
         let f = Float864::from_decimal(&d0, 18).unwrap();
         assert_eq!(f.exponent, 13);
         assert_eq!(f.significand, 123456);
-        let d = f.to_decimal(18);
+        let d = f.to_decimal(18).unwrap();
         assert_eq!(d, Decimal::from_str("1.23456").unwrap());
         let f2 = Float864::decode(&f.encode()).unwrap();
         assert_eq!(f2.exponent, 13);
         assert_eq!(f2.significand, 123456);
     }
+
+    #[test]
+    fn test_float864_arithmetic() {
+        let a = Float864 {
+            exponent: 2,
+            significand: 1,
+        };
+        let b = Float864 {
+            exponent: 0,
+            significand: 100,
+        };
+        assert_eq!(a, b);
+        assert!(a < a.checked_add(b).unwrap());
+        assert_eq!(b.normalize().unwrap().exponent, 2);
+        assert_eq!(
+            a.checked_mul_int(3).unwrap(),
+            Float864 {
+                exponent: 2,
+                significand: 3
+            }
+        );
+        assert!(a.checked_sub(a.checked_mul_int(2).unwrap()).is_err());
+        assert!(a.checked_sub(b).unwrap().significand == 0);
+        let max = Float864 {
+            exponent: 0,
+            significand: Float864::MAX_SIGNIFICAND as u64,
+        };
+        assert!(max.checked_add(max).is_err());
+    }
+
+    #[test]
+    fn test_float864_rounded() {
+        let d = Decimal::from_str("1.5").unwrap();
+        let (f, rem) = Float864::from_decimal_rounded(&d, 0, RoundingMode::HalfEven).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 2));
+        assert_eq!(rem, Decimal::from_str("-0.5").unwrap());
+        let (f, rem) = Float864::from_decimal_rounded(&d, 0, RoundingMode::Down).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 1));
+        assert_eq!(rem, Decimal::from_str("0.5").unwrap());
+        assert!(Float864::from_decimal_rounded(&d, 0, RoundingMode::Exact).is_err());
+
+        let d = Decimal::from_str("1.23456").unwrap();
+        let (f, rem) = Float864::from_decimal_rounded(&d, 18, RoundingMode::Exact).unwrap();
+        assert_eq!((f.exponent, f.significand), (13, 123456));
+        assert!(rem.is_zero());
+    }
+
+    #[test]
+    fn test_float864_errors() {
+        // 2^62 + 1 needs more than 62 bits and can not drop its last digit
+        let d = Decimal::from(4611686018427387905u64);
+        assert!(matches!(
+            Float864::from_decimal(&d, 0),
+            Err(Float864Error::InvalidPrecision(_, 0, _))
+        ));
+        assert!(matches!(
+            Float864::from_decimal(&Decimal::from_str("1.5").unwrap(), 0),
+            Err(Float864Error::Precision(_, 0))
+        ));
+        assert!(matches!(
+            Float864::from_decimal(&Decimal::from_str("-1.5").unwrap(), 18),
+            Err(Float864Error::Negative(_))
+        ));
+        assert!(matches!(
+            Float864::decode(&[13, 0, 0, 0, 0, 0, 1, 0xe2]),
+            Err(Float864Error::InvalidLength(9, 8))
+        ));
+
+        // zero and long runs of trailing zeros stop at the largest exponent
+        let zero = Float864::from_bigint(BigInt::from(0)).unwrap();
+        assert_eq!((zero.exponent, zero.significand), (0, 0));
+        let f = Float864::from_bigint(BigInt::from(10).pow(260u32)).unwrap();
+        assert_eq!((f.exponent, f.significand), (255, 100000));
+        assert!(Float864::from_bigint(BigInt::from(10).pow(300u32)).is_err());
+    }
+
+    #[test]
+    fn test_float864_legacy_encoding() {
+        let f = Float864 {
+            exponent: 13,
+            significand: 123456,
+        };
+        assert_eq!(f.encode(), vec![13, 0, 0, 0, 0, 0, 1, 0xe2, 0x40]);
+        assert_eq!(Float864::ENCODED_BYTES, 9);
+        assert_eq!(Float864::MAX_EXPONENT, u8::MAX);
+        assert_eq!(Float864::MAX_SIGNIFICAND, (u64::MAX / 4) as i128);
+
+        // the exponent of zero is kept
+        let zero = Float864 {
+            exponent: 3,
+            significand: 0,
+        };
+        assert_eq!(zero.encode(), vec![3, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut data = vec![255u8];
+        data.extend_from_slice(&(u64::MAX / 4).to_be_bytes());
+        let f = Float864::decode(&data).unwrap();
+        assert_eq!((f.exponent, f.significand), (255, u64::MAX / 4));
+        assert_eq!(f.encode(), data);
+        assert_eq!(
+            f.to_encoded_int().unwrap(),
+            BigInt::from_bytes_be(num_bigint::Sign::Plus, &data)
+        );
+
+        // significands beyond 62 bits are rejected
+        let mut data = vec![255u8];
+        data.extend_from_slice(&(u64::MAX / 4 + 1).to_be_bytes());
+        assert!(Float864::decode(&data).is_err());
+        let f = Float864 {
+            exponent: 0,
+            significand: u64::MAX,
+        };
+        assert!(f.to_encoded_int().is_err());
+    }
+
+    #[test]
+    fn test_float864_decode_legacy() {
+        // as written by the older codec, followed by unrelated bytes
+        let mut data = vec![13, 0, 0, 0, 0, 0, 1, 0xe2, 0x40];
+        data.extend_from_slice(&[0xff, 0xff]);
+        assert!(Float864::decode(&data).is_err());
+        let f = Float864::decode_legacy(&data).unwrap();
+        assert_eq!((f.exponent, f.significand), (13, 123456));
+        assert_eq!(f.encode(), data[..9]);
+
+        let mut data = vec![2u8];
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        let f = Float864::decode_legacy(&data).unwrap();
+        assert_eq!(f.significand, u64::MAX);
+        assert_eq!(f.to_bigint(), BigInt::from(u64::MAX) * 100);
+        assert!(f.to_encoded_int().is_err());
+
+        assert!(matches!(
+            Float864::decode_legacy(&data[..8]),
+            Err(Float864Error::InvalidLength(9, 8))
+        ));
+    }
 }
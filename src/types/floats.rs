@@ -1,11 +1,76 @@
-use super::{BigInt, Decimal};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+
+use super::{BigInt, Decimal, RoundingMode};
 use crate::num_traits::{identities::Zero, int::PrimInt, FromPrimitive, Pow, Signed, ToPrimitive};
 use crate::types::{Fr, FrExt};
 
+/// Integer types usable as the significand of [`Floats`].
+pub trait Significand: PrimInt {
+    const BITS: u32;
+    const SIGNED: bool;
+}
+
+macro_rules! impl_significand {
+    ($signed:expr, $($t:ty),*) => {
+        $(
+            impl Significand for $t {
+                const BITS: u32 = <$t>::BITS;
+                const SIGNED: bool = $signed;
+            }
+        )*
+    };
+}
+
+impl_significand!(true, i8, i16, i32, i64, i128);
+impl_significand!(false, u8, u16, u32, u64, u128);
+
+/// Byte layouts of the [`Floats`] encoding, selected by its `LAYOUT` parameter.
+pub mod layout {
+    /// Exponent and significand packed back to back, the bits for exponent is
+    /// 8 - NBITS % 8 so the encoding is aligned to the byte edge.
+    pub const PACKED: u8 = 0;
+    /// A whole exponent byte followed by the significand rounded up to whole bytes,
+    /// as used by [`Float864`](crate::types::Float864).
+    pub const BYTE_ALIGNED: u8 = 1;
+}
+
 /// a POSTIVE float representation with 1 byte exponent and NBITS significand, the bits for exponent is 8 - NBITS % 8
 //  so total bits for encoding a number would be always aligned to the byte edge
+///
+/// `NBITS` must be between 1 and the bit width of `T` minus 1, and `LAYOUT` one of the
+/// [`layout`] consts, otherwise the associated consts, every constructor and every
+/// conversion of the value fail to compile:
+///
+/// ```compile_fail
+/// use fluidex_common::types::Floats;
+///
+/// // no bit left for the sign of i8
+/// let _ = Floats::<i8, 8>::zero();
+/// ```
+///
+/// ```compile_fail
+/// use fluidex_common::types::{BigInt, Floats};
+///
+/// let _ = Floats::<u32, 0>::from_bigint(BigInt::from(1));
+/// ```
+///
+/// ```compile_fail
+/// use fluidex_common::types::Floats;
+///
+/// // struct literals bypass the constructors, but not the conversions
+/// let f = Floats::<u64, 64> { exponent: 0, significand: 1 };
+/// let _ = f.to_bigint();
+/// ```
+///
+/// ```compile_fail
+/// use fluidex_common::types::Floats;
+///
+/// let _ = Floats::<i64, 35, 7>::zero();
+/// ```
 #[derive(Debug, Clone, Copy)]
-pub struct Floats<T: PrimInt, const NBITS: usize> {
+pub struct Floats<T: Significand, const NBITS: usize, const LAYOUT: u8 = { layout::PACKED }> {
     pub exponent: u8,
     //represent a unsigned int in little-endian fashion (last element for the most signifcant byte)
     pub significand: T,
@@ -27,25 +92,198 @@ pub enum FloatsError {
     ExponentTooBig,
     #[error("number {0} too big to be saved")]
     NumberTooBig(BigInt),
+    #[error("negative value {0} for an unsigned significand")]
+    Negative(Decimal),
+    #[error("remainder of rounding {0} is not representable as decimal")]
+    Remainder(Decimal),
+    #[error("{0} with precision {1} is not representable as decimal")]
+    DecimalOverflow(BigInt, u32),
+    #[error("expected {0} encoded bytes, got {1}")]
+    InvalidLength(usize, usize),
+    #[error("encoded value {0} exceeds {1} bits")]
+    EncodingOverflow(BigInt, usize),
+    #[error("negative encoded value {0}")]
+    NegativeEncoding(BigInt),
 }
 
 type Result<T, E = FloatsError> = std::result::Result<T, E>;
 
-impl<T: PrimInt + Zero, const NBITS: usize> Floats<T, NBITS> {
-    fn sig_to_bigint(self) -> BigInt {
+/// The result of a lossy conversion: the closest representable value and what was lost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lossy<F, E> {
+    pub value: F,
+    /// `input - value`
+    pub abs_error: E,
+    /// `|abs_error / input|`, 0 for a zero input.
+    pub rel_error: f64,
+}
+
+/// Move the trailing zeros of `significand` into the exponent, up to `max_exp`.
+/// Zero always gets exponent 0.
+pub(crate) fn strip_zeros(
+    mut significand: BigInt,
+    mut exponent: u32,
+    max_exp: u32,
+) -> (BigInt, u32) {
+    if significand.is_zero() {
+        return (significand, 0);
+    }
+    while exponent < max_exp && (&significand % 10u32).is_zero() {
+        significand /= 10u32;
+        exponent += 1;
+    }
+    (significand, exponent)
+}
+
+/// `n / 10^scale` as a Decimal, `None` if it needs more than 96 bits or 28 decimal places.
+fn bigint_to_decimal(n: &BigInt, scale: u32) -> Option<Decimal> {
+    let mut d = n.to_i128().and_then(Decimal::from_i128)?;
+    d.set_scale(scale).ok()?;
+    Some(d)
+}
+
+/// Round `d * 10^prec` to `significand * 10^exponent`, with the significand between `low`
+/// and `high` and the smallest possible exponent up to `max_exp`, and trailing zeros of
+/// the significand moved into the exponent.
+///
+/// Returns the significand, the exponent and the remainder lost by rounding,
+/// `d - significand * 10^(exponent - prec)`.
+pub(crate) fn round_decimal(
+    d: &Decimal,
+    prec: u32,
+    rounding: RoundingMode,
+    (low, high): (&BigInt, &BigInt),
+    max_exp: u32,
+) -> Result<(BigInt, u32, Decimal)> {
+    // work in units of 10^-scale so both d and the rounded value are integers
+    let scale = d.scale().max(prec);
+    let value = BigInt::from(d.mantissa()) * BigInt::from(10).pow(scale - d.scale());
+    let unit = BigInt::from(10).pow(scale - prec);
+    // rounding toward zero would otherwise turn a small negative value into zero
+    if value.is_negative() && !low.is_negative() {
+        return Err(FloatsError::Negative(*d));
+    }
+
+    for exponent in 0..=max_exp {
+        let denom = &unit * BigInt::from(10).pow(exponent);
+        let significand = match rounding.div(&value, &denom) {
+            Some(significand) => significand,
+            None if exponent == 0 => return Err(FloatsError::Precision(*d, prec)),
+            // an integer at exponent 0, but with more digits than the significand holds
+            None => {
+                let (n, _) = strip_zeros(&value / &unit, 0, u32::MAX);
+                return Err(match bigint_to_decimal(&n, 0) {
+                    Some(n) => FloatsError::InvalidPrecision(*d, prec, n),
+                    None => FloatsError::NumberTooBig(n),
+                });
+            }
+        };
+        if significand < *low || significand > *high {
+            continue;
+        }
+
+        let (significand, exponent) = strip_zeros(significand, exponent, max_exp);
+        let lost = value - &significand * BigInt::from(10).pow(exponent) * &unit;
+        let remainder = bigint_to_decimal(&lost, scale).ok_or(FloatsError::Remainder(*d))?;
+        return Ok((significand, exponent, remainder));
+    }
+
+    Err(FloatsError::NumberTooBig(value / unit))
+}
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Floats<T, NBITS, LAYOUT> {
+    const VALID_NBITS: () = {
+        assert!(
+            NBITS > 0 && NBITS < T::BITS as usize,
+            "NBITS must be between 1 and the bit width of T minus 1"
+        );
+        assert!(
+            LAYOUT == layout::PACKED || LAYOUT == layout::BYTE_ALIGNED,
+            "unknown LAYOUT"
+        );
+    };
+    /// Bits used by the exponent in the encoding.
+    pub const EXPONENT_BITS: usize = {
+        let () = Self::VALID_NBITS;
+        match LAYOUT {
+            layout::BYTE_ALIGNED => 8,
+            _ => 8 - NBITS % 8,
+        }
+    };
+    /// Bits used by the significand in the encoding, at least `NBITS`.
+    pub const SIGNIFICAND_BITS: usize = {
+        let () = Self::VALID_NBITS;
+        match LAYOUT {
+            layout::BYTE_ALIGNED => (NBITS + 7) / 8 * 8,
+            _ => NBITS,
+        }
+    };
+    pub const MAX_EXPONENT: u8 = ((1u16 << Self::EXPONENT_BITS) - 1) as u8;
+    /// Length of [`Floats::encode`] output.
+    pub const ENCODED_BYTES: usize = (Self::SIGNIFICAND_BITS + Self::EXPONENT_BITS) / 8;
+    pub const MAX_SIGNIFICAND: i128 = {
+        let () = Self::VALID_NBITS;
+        if T::SIGNED {
+            (1i128 << (NBITS - 1)) - 1
+        } else {
+            ((1u128 << NBITS) - 1) as i128
+        }
+    };
+    pub const MIN_SIGNIFICAND: i128 = {
+        let () = Self::VALID_NBITS;
+        if T::SIGNED {
+            -(1i128 << (NBITS - 1))
+        } else {
+            0
+        }
+    };
+
+    fn prim_to_bigint(x: T) -> BigInt {
         //cast to the largest int (128bit) possible
         if T::min_value() < T::zero() {
-            BigInt::from(self.significand.to_i128().unwrap())
+            BigInt::from(x.to_i128().unwrap())
         } else {
-            BigInt::from(self.significand.to_u128().unwrap())
+            BigInt::from(x.to_u128().unwrap())
         }
     }
 
+    /// Cast back from BigInt, `None` if `bi` does not fit in `T`.
+    fn bigint_to_prim(bi: &BigInt) -> Option<T> {
+        if T::min_value() < T::zero() {
+            bi.to_i128().and_then(T::from)
+        } else {
+            bi.to_u128().and_then(T::from)
+        }
+    }
+
+    fn sig_to_bigint(self) -> BigInt {
+        let () = Self::VALID_NBITS;
+        Self::prim_to_bigint(self.significand)
+    }
+
+    fn max_exp() -> u32 {
+        Self::MAX_EXPONENT as u32
+    }
+
+    /// The smallest and largest significand that fits in NBITS.
+    fn significand_bounds() -> (BigInt, BigInt) {
+        (
+            BigInt::from(Self::MIN_SIGNIFICAND),
+            BigInt::from(Self::MAX_SIGNIFICAND),
+        )
+    }
+
+    fn significand_in_range(significand: &BigInt) -> bool {
+        let (low, high) = Self::significand_bounds();
+        low <= *significand && *significand <= high
+    }
+
     pub fn encode_len() -> usize {
-        (NBITS + 8 - NBITS % 8) / 8
+        Self::ENCODED_BYTES
     }
 
     pub fn zero() -> Self {
+        let () = Self::VALID_NBITS;
         Self {
             significand: T::zero(),
             exponent: 0,
@@ -59,20 +297,24 @@ impl<T: PrimInt + Zero, const NBITS: usize> Floats<T, NBITS> {
 
     pub fn to_encoded_int(self) -> Result<BigInt> {
         //cast to the largest int (128bit) possible
-        let max_exp = (1 << (8 - NBITS % 8)) - 1;
-        if self.exponent > max_exp as u8 {
+        if self.exponent > Self::MAX_EXPONENT {
             return Err(FloatsError::ExponentTooBig);
         }
 
-        if self.significand == T::zero() {
+        // the byte aligned layout keeps the exponent of zero, as the legacy Float864 codec did
+        if self.significand == T::zero() && LAYOUT == layout::PACKED {
             return Ok(BigInt::zero());
         }
 
+        let sig_bits = Self::SIGNIFICAND_BITS;
         let sig = self.sig_to_bigint();
-        if sig.is_positive() {
-            Ok((BigInt::from(self.exponent) << NBITS) + sig)
+        if !Self::significand_in_range(&sig) {
+            return Err(FloatsError::NumberTooBig(sig));
+        }
+        if sig.is_negative() {
+            Ok((BigInt::from(self.exponent) << sig_bits) + ((BigInt::from(1) << sig_bits) + sig))
         } else {
-            Ok((BigInt::from(self.exponent) << NBITS) + ((BigInt::from(1) << NBITS) + sig))
+            Ok((BigInt::from(self.exponent) << sig_bits) + sig)
         }
     }
 
@@ -80,39 +322,72 @@ impl<T: PrimInt + Zero, const NBITS: usize> Floats<T, NBITS> {
         Fr::from_bigint(self.to_bigint())
     }
 
-    //encode to big-endian bytes, with the exponent parts at the beginning
-    //suppose it could be accommodate to an u128 integer
+    /// The encoded integer as a field element, as circuits consume it.
+    pub fn to_encoded_fr(self) -> Result<Fr> {
+        Ok(Fr::from_bigint(self.to_encoded_int()?))
+    }
+
+    /// Inverse of [`Floats::to_encoded_fr`], rejecting field elements with bits set beyond
+    /// `encode_len() * 8`.
+    pub fn from_encoded_fr(fr: &Fr) -> Result<Self> {
+        let bi = fr.to_bigint();
+        let bits = Self::encode_len() * 8;
+        if bi.bits() > bits as u64 {
+            return Err(FloatsError::EncodingOverflow(bi, bits));
+        }
+        Self::from_encoded_bigint(bi)
+    }
+
+    /// [`Floats::try_encode`], panicking if the value can not be encoded.
     pub fn encode(self) -> Vec<u8> {
-        let bi = self.to_encoded_int().unwrap();
+        self.try_encode().unwrap()
+    }
+
+    /// [`Floats::ENCODED_BYTES`] bytes, failing like [`Floats::to_encoded_int`].
+    pub fn try_encode(self) -> Result<Vec<u8>> {
+        //encode to big-endian bytes, with the exponent parts at the beginning
+        //suppose it could be accommodate to an u128 integer
+        let bi = self.to_encoded_int()?;
         let (_, mut bytes) = bi.to_bytes_be();
 
         let mut head_zeros = vec![0u8; Self::encode_len() - bytes.len()];
         head_zeros.append(&mut bytes);
-        head_zeros
+        Ok(head_zeros)
     }
 
+    /// Inverse of [`Floats::to_encoded_int`], failing on a negative input, an exponent
+    /// above [`Floats::MAX_EXPONENT`] or a significand out of range.
     pub fn from_encoded_bigint(bi: BigInt) -> Result<Self> {
-        assert!(NBITS < 120);
+        let () = Self::VALID_NBITS;
 
-        //we do not need the signed for encoded integer
-        let bi = if bi.is_positive() { bi } else { -bi };
+        if bi.is_negative() {
+            return Err(FloatsError::NegativeEncoding(bi));
+        }
 
-        let signi_mask: BigInt = (BigInt::from(1) << NBITS) - 1;
+        let sig_bits = Self::SIGNIFICAND_BITS;
+        let signi_mask: BigInt = (BigInt::from(1) << sig_bits) - 1;
         let significand = &bi & &signi_mask;
-        let exponent = &bi >> NBITS;
+        let exponent = &bi >> sig_bits;
         let significand = if T::min_value() < T::zero() {
-            let signed_max = BigInt::from(1) << (NBITS - 1);
-            if significand <= signed_max {
-                T::from(significand.to_i128().unwrap()).ok_or(FloatsError::NumberTooBig(bi))?
+            let signed_max = BigInt::from(1) << (sig_bits - 1);
+            if significand < signed_max {
+                significand
             } else {
-                let significand = -(signi_mask - significand + BigInt::from(1));
-                T::from(significand.to_i128().unwrap()).ok_or(FloatsError::NumberTooBig(bi))?
+                -(signi_mask - significand + BigInt::from(1))
             }
         } else {
-            T::from(significand.to_u128().unwrap()).ok_or(FloatsError::NumberTooBig(bi))?
+            significand
         };
+        if !Self::significand_in_range(&significand) {
+            return Err(FloatsError::NumberTooBig(bi));
+        }
+        let significand =
+            Self::bigint_to_prim(&significand).ok_or(FloatsError::NumberTooBig(bi))?;
 
-        let exponent = exponent.to_u8().ok_or(FloatsError::ExponentTooBig)?;
+        let exponent = exponent
+            .to_u8()
+            .filter(|exponent| *exponent <= Self::MAX_EXPONENT)
+            .ok_or(FloatsError::ExponentTooBig)?;
 
         Ok(Self {
             exponent,
@@ -121,118 +396,191 @@ impl<T: PrimInt + Zero, const NBITS: usize> Floats<T, NBITS> {
     }
 
     pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() != Self::ENCODED_BYTES {
+            return Err(FloatsError::InvalidLength(Self::ENCODED_BYTES, data.len()));
+        }
         Self::from_encoded_bigint(BigInt::from_bytes_be(num_bigint::Sign::Plus, data))
     }
 
-    pub fn to_decimal(self, prec: u32) -> Decimal {
+    /// The value divided by `10^prec`, failing if it does not fit in a Decimal.
+    pub fn to_decimal(self, prec: u32) -> Result<Decimal> {
         // for example, (significand:1, exponent:17) means 10**17, when prec is 18,
         // it is 0.1 (ETH)
-        //TODO: demical can not handle full 128bit integer so we need to verify that,
-        //in case the significand can be hold by Decimal it must be able to be converted
-        //into i128 safetily
-        let mut ret = Decimal::from_i128(self.significand.to_i128().unwrap()).unwrap();
-        if (self.exponent as u32) < prec {
-            ret.set_scale(prec - self.exponent as u32).unwrap();
-        } else {
-            ret *= Decimal::new(10, 0).pow(self.exponent as u64 - prec as u64);
+        let value = self.to_bigint();
+        let mut mantissa = value.clone();
+        let mut scale = prec;
+        // keep `prec` decimal places unless the mantissa is too wide for Decimal
+        loop {
+            if let Some(d) = bigint_to_decimal(&mantissa, scale) {
+                return Ok(d);
+            }
+            if scale == 0 || !(&mantissa % 10u32).is_zero() {
+                return Err(FloatsError::DecimalOverflow(value, prec));
+            }
+            mantissa /= 10u32;
+            scale -= 1;
         }
-
-        ret.rescale(prec);
-        ret
     }
 
+    /// The representation of `bi` with the smallest possible significand.
     pub fn from_bigint(bi: BigInt) -> Result<Self> {
-        let eff_bits = T::zero().count_zeros() as usize;
-        assert!(eff_bits > NBITS && eff_bits <= 128);
-
-        //TODO: we are not able to handle T as u128 yet
-        let test_low_bound = T::min_value() >> (eff_bits - NBITS);
-        let test_high_bound = T::max_value() >> (eff_bits - NBITS);
-
-        let max_exp = (1 << (8 - NBITS % 8)) - 1;
-
-        let mut exponent = 0u8;
-        let mut encode_int = bi.clone();
-        let mut test_sig: BigInt = bi.clone() / 10;
-        while encode_int == test_sig.clone() * 10 && exponent <= max_exp as u8 {
-            encode_int = test_sig;
-            exponent += 1;
-            test_sig = encode_int.clone() / 10;
+        let () = Self::VALID_NBITS;
+        let (low, high) = Self::significand_bounds();
+        // stops at MAX_EXPONENT, so 10^255 and zero can not overflow the u8 exponent
+        let (significand, exponent) = strip_zeros(bi.clone(), 0, Self::max_exp());
+        if significand < low || significand > high {
+            return Err(FloatsError::NumberTooBig(bi));
         }
+        Ok(Self {
+            exponent: exponent as u8,
+            significand: Self::bigint_to_prim(&significand).ok_or(FloatsError::NumberTooBig(bi))?,
+        })
+    }
+
+    /// Convert from Decimal with `prec` decimal places, failing if the value is not exactly
+    /// representable, see [`Floats::from_decimal_rounded`] to round instead.
+    pub fn from_decimal(d: &Decimal, prec: u32) -> Result<Self> {
+        let (f, _) = Self::from_decimal_rounded(d, prec, RoundingMode::Exact)?;
+        Ok(f)
+    }
+
+    /// Convert from Decimal, rounding to the closest representable value in the direction
+    /// given by `rounding`.
+    ///
+    /// Returns the value and the remainder lost by rounding, `d - value.to_decimal(prec)`.
+    pub fn from_decimal_rounded(
+        d: &Decimal,
+        prec: u32,
+        rounding: RoundingMode,
+    ) -> Result<(Self, Decimal)> {
+        let () = Self::VALID_NBITS;
+        let (low, high) = Self::significand_bounds();
+        let (significand, exponent, remainder) =
+            round_decimal(d, prec, rounding, (&low, &high), Self::max_exp())?;
 
         let significand = if T::min_value() < T::zero() {
-            T::from(
-                encode_int
-                    .to_i128()
-                    .ok_or_else(|| FloatsError::NumberTooBig(bi.clone()))?,
-            )
-            .ok_or_else(|| FloatsError::NumberTooBig(bi.clone()))?
+            significand.to_i128().and_then(T::from)
         } else {
-            T::from(
-                encode_int
-                    .to_u128()
-                    .ok_or_else(|| FloatsError::NumberTooBig(bi.clone()))?,
-            )
-            .ok_or_else(|| FloatsError::NumberTooBig(bi.clone()))?
-        };
+            significand.to_u128().and_then(T::from)
+        }
+        .ok_or_else(|| FloatsError::NumberTooBig(significand.clone()))?;
 
-        if significand > test_high_bound || significand < test_low_bound {
-            Err(FloatsError::NumberTooBig(bi))
-        } else {
-            Ok(Self {
-                exponent,
+        Ok((
+            Self {
+                exponent: exponent as u8,
                 significand,
-            })
-        }
+            },
+            remainder,
+        ))
     }
+}
 
-    //update from Decimal and round
-    pub fn from_decimal(d: &Decimal, prec: u32) -> Result<Self> {
-        let eff_bits = T::zero().count_zeros() as usize;
-        assert!(eff_bits > NBITS && eff_bits <= 128);
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Floats<T, NBITS, LAYOUT> {
+    /// The same value with trailing zeros of the significand moved into the exponent,
+    /// so `(10, 0)` becomes `(1, 1)`. Fails if the value is not representable at all.
+    pub fn normalize(self) -> Result<Self> {
+        Self::from_bigint(self.to_bigint())
+    }
 
-        //TODO: we are not able to handle T as u128 yet
-        let test_low_bound = (T::min_value() >> (eff_bits - NBITS)).to_i128().unwrap();
-        let test_high_bound = (T::max_value() >> (eff_bits - NBITS)).to_i128().unwrap();
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        Self::from_bigint(self.to_bigint() + other.to_bigint())
+    }
 
-        if d.is_zero() {
-            return Ok(Self {
-                exponent: 0,
-                significand: T::zero(),
+    pub fn checked_sub(self, other: Self) -> Result<Self> {
+        Self::from_bigint(self.to_bigint() - other.to_bigint())
+    }
+
+    pub fn checked_mul_int<I: Into<BigInt>>(self, n: I) -> Result<Self> {
+        Self::from_bigint(self.to_bigint() * n.into())
+    }
+
+    /// The smallest and largest integer a float of this layout can hold.
+    ///
+    /// Not every integer inside the range is representable, only those with at most
+    /// `NBITS` significant bits once trailing zeros are dropped.
+    pub fn representable_range() -> RangeInclusive<BigInt> {
+        let scale = BigInt::from(10).pow(Self::MAX_EXPONENT);
+        let (low, high) = Self::significand_bounds();
+        (low * &scale)..=(high * scale)
+    }
+
+    /// Convert from BigInt, rounding to the closest representable value (ties to even)
+    /// instead of failing when the significand does not fit.
+    ///
+    /// Fails only if `bi` is outside [`Floats::representable_range`].
+    pub fn from_bigint_lossy(bi: BigInt) -> Result<Lossy<Self, BigInt>> {
+        let () = Self::VALID_NBITS;
+        let (low, high) = Self::significand_bounds();
+        for exponent in 0..=Self::max_exp() {
+            let significand = RoundingMode::HalfEven
+                .div(&bi, &BigInt::from(10).pow(exponent))
+                .expect("HalfEven never fails");
+            if significand < low || significand > high {
+                continue;
+            }
+
+            let (significand, exponent) = strip_zeros(significand, exponent, Self::max_exp());
+            let value = Self {
+                exponent: exponent as u8,
+                significand: Self::bigint_to_prim(&significand)
+                    .ok_or_else(|| FloatsError::NumberTooBig(bi.clone()))?,
+            };
+            let abs_error = &bi - value.to_bigint();
+            let rel_error = if bi.is_zero() {
+                0.0
+            } else {
+                (abs_error.to_f64().unwrap_or(f64::NAN) / bi.to_f64().unwrap_or(f64::NAN)).abs()
+            };
+            return Ok(Lossy {
+                value,
+                abs_error,
+                rel_error,
             });
         }
-        // if d is "0.1" and prec is 18, result is (significand:1, exponent:17)
-        let mut n = *d;
-        let mut exponent = if n.scale() < prec {
-            prec - d.scale()
-        } else {
-            0u32
-        };
 
-        n.set_scale(if n.scale() < prec {
-            0
+        Err(FloatsError::NumberTooBig(bi))
+    }
+
+    /// Convert from Decimal with `prec` decimal places, rounding to the closest
+    /// representable value (ties to even).
+    pub fn from_decimal_lossy(d: &Decimal, prec: u32) -> Result<Lossy<Self, Decimal>> {
+        let (value, abs_error) = Self::from_decimal_rounded(d, prec, RoundingMode::HalfEven)?;
+        let rel_error = if d.is_zero() {
+            0.0
         } else {
-            n.scale() - prec
+            (abs_error / *d).abs().to_f64().unwrap_or(f64::NAN)
+        };
+        Ok(Lossy {
+            value,
+            abs_error,
+            rel_error,
         })
-        .map_err(FloatsError::Demical)?;
-        if n != n.floor() {
-            return Err(FloatsError::Precision(n, prec));
-        }
-        let mut test = n.to_i128().unwrap();
-        loop {
-            let next = test / 10i128;
-            if test > test_high_bound || test < test_low_bound || next * 10i128 == test {
-                exponent += 1;
-                test = next;
-            } else {
-                break;
-            }
-        }
+    }
+}
 
-        Ok(Self {
-            exponent: exponent as u8,
-            significand: T::from(test).unwrap(),
-        })
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> PartialEq for Floats<T, NBITS, LAYOUT> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bigint() == other.to_bigint()
+    }
+}
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Eq for Floats<T, NBITS, LAYOUT> {}
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> PartialOrd for Floats<T, NBITS, LAYOUT> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Ord for Floats<T, NBITS, LAYOUT> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_bigint().cmp(&other.to_bigint())
+    }
+}
+
+impl<T: Significand, const NBITS: usize, const LAYOUT: u8> Hash for Floats<T, NBITS, LAYOUT> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bigint().hash(state);
     }
 }
 
@@ -350,8 +698,12 @@ mod tests {
             BigInt::from_str("-18330000000000000000000000000000000000").unwrap(),
         )
         .unwrap();
-        assert_eq!(m5.exponent, 32);
-        assert_eq!(m5.significand, -183300);
+        assert_eq!(m5.exponent, 31);
+        assert_eq!(m5.significand, -1833000);
+        assert!(m5.to_encoded_int().is_ok());
+
+        let zero = Float40::from_bigint(BigInt::from(0)).unwrap();
+        assert_eq!((zero.exponent, zero.significand), (0, 0));
     }
 
     #[test]
@@ -512,64 +864,307 @@ mod tests {
         let p1 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(p1.exponent, 7);
         assert_eq!(p1.significand, 1);
-        assert_eq!(d, p1.to_decimal(4));
+        assert_eq!(d, p1.to_decimal(4).unwrap());
         let d = Decimal::new(1000, 2);
         let p2 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(p2.exponent, 5);
         assert_eq!(p2.significand, 1);
-        assert_eq!(d, p2.to_decimal(4));
+        assert_eq!(d, p2.to_decimal(4).unwrap());
         let d = Decimal::new(1000000, 6);
         let p3 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(p3.exponent, 4);
         assert_eq!(p3.significand, 1);
-        assert_eq!(d, p3.to_decimal(4));
+        assert_eq!(d, p3.to_decimal(4).unwrap());
         let d = Decimal::new(12345678, 6);
         let p4 = Float40::from_decimal(&d, 8).unwrap();
         assert_eq!(p4.exponent, 2);
         assert_eq!(p4.significand, 12345678);
-        assert_eq!(d, p4.to_decimal(8));
+        assert_eq!(d, p4.to_decimal(8).unwrap());
         let d = Decimal::new(1000000000000i64, 0);
         let p5 = Float40::from_decimal(&d, 2).unwrap();
         assert_eq!(p5.exponent, 14);
         assert_eq!(p5.significand, 1);
-        assert_eq!(d, p5.to_decimal(2));
+        assert_eq!(d, p5.to_decimal(2).unwrap());
+        // 1234560 does not fit in 16 bits without dropping a digit
         let d = Decimal::new(123456, 5);
+        assert!(Floats::<u32, 16>::from_decimal(&d, 6).is_err());
+        assert!(Floats::<i32, 16>::from_decimal(&d, 6).is_err());
+        let d = Decimal::new(12345, 4);
         let r5 = Floats::<u32, 16>::from_decimal(&d, 6).unwrap();
         assert_eq!(r5.exponent, 2);
         assert_eq!(r5.significand, 12345);
-        let r5 = Floats::<i32, 16>::from_decimal(&d, 6).unwrap();
-        assert_eq!(r5.exponent, 2);
-        assert_eq!(r5.significand, 12345);
+        let d = Decimal::new(100, 0);
+        assert!(matches!(
+            Floats::<u32, 16>::from_decimal(&d, 300),
+            Err(FloatsError::NumberTooBig(_))
+        ));
 
         let d = Decimal::new(-1000, 0);
         let m1 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(m1.exponent, 7);
         assert_eq!(m1.significand, -1);
-        assert_eq!(d, m1.to_decimal(4));
+        assert_eq!(d, m1.to_decimal(4).unwrap());
         let d = Decimal::new(-1000, 2);
         let m2 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(m2.exponent, 5);
         assert_eq!(m2.significand, -1);
-        assert_eq!(d, m2.to_decimal(4));
+        assert_eq!(d, m2.to_decimal(4).unwrap());
         let d = Decimal::new(-1000000, 6);
         let m3 = Float40::from_decimal(&d, 4).unwrap();
         assert_eq!(m3.exponent, 4);
         assert_eq!(m3.significand, -1);
-        assert_eq!(d, m3.to_decimal(4));
+        assert_eq!(d, m3.to_decimal(4).unwrap());
         let d = Decimal::new(-12345678, 6);
         let m4 = Float40::from_decimal(&d, 8).unwrap();
         assert_eq!(m4.exponent, 2);
         assert_eq!(m4.significand, -12345678);
-        assert_eq!(d, m4.to_decimal(8));
+        assert_eq!(d, m4.to_decimal(8).unwrap());
         let d = Decimal::new(-1000000000000i64, 0);
         let m5 = Float40::from_decimal(&d, 2).unwrap();
         assert_eq!(m5.exponent, 14);
         assert_eq!(m5.significand, -1);
-        assert_eq!(d, m5.to_decimal(2));
+        assert_eq!(d, m5.to_decimal(2).unwrap());
         let d = Decimal::new(-123456, 5);
+        assert!(Floats::<i32, 16>::from_decimal(&d, 6).is_err());
+        let d = Decimal::new(-12345, 4);
         let mr5 = Floats::<i32, 16>::from_decimal(&d, 6).unwrap();
         assert_eq!(mr5.exponent, 2);
         assert_eq!(mr5.significand, -12345);
+        assert!(matches!(
+            Floats::<u32, 16>::from_decimal(&d, 6),
+            Err(FloatsError::Negative(_))
+        ));
+    }
+
+    #[test]
+    fn test_decimal_rounded() {
+        let d = Decimal::from_str("1.234567").unwrap();
+        let (f, rem) = Float40::from_decimal_rounded(&d, 2, RoundingMode::Down).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 123));
+        assert_eq!(rem, Decimal::from_str("0.004567").unwrap());
+        let (f, rem) = Float40::from_decimal_rounded(&d, 2, RoundingMode::Up).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 124));
+        assert_eq!(rem, Decimal::from_str("-0.005433").unwrap());
+        assert_eq!(f.to_decimal(2).unwrap() + rem, d);
+        let (f, _) = Float40::from_decimal_rounded(&d, 4, RoundingMode::HalfEven).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 12346));
+        let (f, rem) = Float40::from_decimal_rounded(&d, 6, RoundingMode::Exact).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 1234567));
+        assert!(rem.is_zero());
+        assert!(matches!(
+            Float40::from_decimal_rounded(&d, 2, RoundingMode::Exact),
+            Err(FloatsError::Precision(_, 2))
+        ));
+
+        // the significand overflows the bound -17179869183 ~ 17179869183
+        let d = Decimal::new(159999999999, 0);
+        let (f, rem) = Float40::from_decimal_rounded(&d, 0, RoundingMode::Down).unwrap();
+        assert_eq!((f.exponent, f.significand), (1, 15999999999));
+        assert_eq!(rem, Decimal::new(9, 0));
+        let (f, rem) = Float40::from_decimal_rounded(&d, 0, RoundingMode::HalfEven).unwrap();
+        assert_eq!((f.exponent, f.significand), (10, 16));
+        assert_eq!(rem, Decimal::new(-1, 0));
+        assert!(Float40::from_decimal_rounded(&d, 0, RoundingMode::Exact).is_err());
+        let (f, _) = Float40::from_decimal_rounded(&-d, 0, RoundingMode::Down).unwrap();
+        assert_eq!((f.exponent, f.significand), (1, -15999999999));
+
+        let d = Decimal::new(123456, 5);
+        let (f, rem) = Floats::<u32, 16>::from_decimal_rounded(&d, 6, RoundingMode::Down).unwrap();
+        assert_eq!((f.exponent, f.significand), (2, 12345));
+        assert_eq!(rem, Decimal::from_str("0.00006").unwrap());
+        // truncating -1234560 to 16 bits would give 0 at exponent 7
+        assert!(matches!(
+            Floats::<u32, 16>::from_decimal_rounded(&-d, 6, RoundingMode::Down),
+            Err(FloatsError::Negative(_))
+        ));
+
+        let (f, rem) =
+            Float40::from_decimal_rounded(&Decimal::new(0, 0), 18, RoundingMode::Exact).unwrap();
+        assert_eq!((f.exponent, f.significand), (0, 0));
+        assert!(rem.is_zero());
+    }
+
+    #[test]
+    fn test_128bits() {
+        type U128Float = Floats<u128, 127>;
+        type I128Float = Floats<i128, 127>;
+        assert_eq!(U128Float::encode_len(), 16);
+
+        let max = BigInt::from(u128::MAX >> 1);
+        let f = U128Float::from_bigint(max.clone() * 10).unwrap();
+        assert_eq!(f.exponent, 1);
+        assert_eq!(f.significand, u128::MAX >> 1);
+        assert_eq!(f.to_bigint(), max.clone() * 10);
+        let decoded = U128Float::decode(&f.encode()).unwrap();
+        assert_eq!(decoded.exponent, 1);
+        assert_eq!(decoded.significand, u128::MAX >> 1);
+        U128Float::from_bigint(max + 1).expect_err("expect too big error");
+
+        let min = BigInt::from(i128::MIN >> 1);
+        let f = I128Float::from_bigint(min.clone()).unwrap();
+        assert_eq!(f.significand, i128::MIN >> 1);
+        let f = I128Float::from_bigint(BigInt::from(-10)).unwrap();
+        assert_eq!(f.to_encoded_int().unwrap(), (BigInt::from(1) << 128) - 1);
+        let decoded = I128Float::decode(&f.encode()).unwrap();
+        assert_eq!(decoded.exponent, 1);
+        assert_eq!(decoded.significand, -1);
+        I128Float::from_bigint(min - 1).expect_err("expect too big error");
+
+        let d = Decimal::from_str("-79228162514264337593543950335").unwrap();
+        let f = I128Float::from_decimal(&d, 0).unwrap();
+        assert_eq!(f.exponent, 0);
+        assert_eq!(f.significand, -79228162514264337593543950335i128);
+        let d = Decimal::from_str("1.5").unwrap();
+        let f = U128Float::from_decimal(&d, 2).unwrap();
+        assert_eq!(f.exponent, 1);
+        assert_eq!(f.significand, 15);
+
+        // Decimal holds at most 96 bits and 28 decimal places
+        let f = U128Float {
+            exponent: 0,
+            significand: (1u128 << 96) - 1,
+        };
+        assert_eq!(f.to_decimal(0).unwrap(), Decimal::MAX);
+        assert_eq!(
+            f.to_decimal(2).unwrap(),
+            Decimal::from_str("792281625142643375935439503.35").unwrap()
+        );
+        let f = I128Float {
+            exponent: 0,
+            significand: -((1i128 << 96) - 1),
+        };
+        assert_eq!(f.to_decimal(0).unwrap(), Decimal::MIN);
+        let f = U128Float {
+            exponent: 0,
+            significand: 1u128 << 96,
+        };
+        assert!(matches!(
+            f.to_decimal(0),
+            Err(FloatsError::DecimalOverflow(_, 0))
+        ));
+        assert!(U128Float {
+            exponent: 0,
+            significand: u128::MAX >> 1,
+        }
+        .to_decimal(0)
+        .is_err());
+        let f = U128Float {
+            exponent: 28,
+            significand: 8,
+        };
+        assert_eq!(
+            f.to_decimal(30).unwrap(),
+            Decimal::from_str("0.08").unwrap()
+        );
+        assert!(f.to_decimal(0).is_err());
+        // 10^-40 needs more than 28 decimal places
+        assert!(U128Float {
+            exponent: 0,
+            significand: 1,
+        }
+        .to_decimal(40)
+        .is_err());
+    }
+
+    #[test]
+    fn test_value_semantics() {
+        use std::collections::HashSet;
+
+        let a = Float40 {
+            exponent: 1,
+            significand: 1,
+        };
+        let b = Float40 {
+            exponent: 0,
+            significand: 10,
+        };
+        assert_eq!(a, b);
+        assert_eq!(HashSet::from([a, b]).len(), 1);
+        assert_eq!(b.normalize().unwrap().exponent, 1);
+        assert!(Float40::zero() < a);
+        assert!(
+            Float40 {
+                exponent: 2,
+                significand: -1
+            } < Float40::zero()
+        );
+        assert_eq!(
+            Float40 {
+                exponent: 0,
+                significand: 0
+            },
+            Float40 {
+                exponent: 5,
+                significand: 0
+            }
+        );
+        assert_eq!(
+            Float40 {
+                exponent: 5,
+                significand: 0
+            }
+            .normalize()
+            .unwrap()
+            .exponent,
+            0
+        );
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Float40::from_bigint(BigInt::from(1200)).unwrap();
+        let b = Float40::from_bigint(BigInt::from(34)).unwrap();
+        let sum = a.checked_add(b).unwrap();
+        assert_eq!(sum.to_bigint(), BigInt::from(1234));
+        assert_eq!(b.checked_sub(a).unwrap().to_bigint(), BigInt::from(-1166));
+        let product = b.checked_mul_int(1000).unwrap();
+        assert_eq!((product.exponent, product.significand), (3, 34));
+
+        // 17179869183 is the largest significand, 10^31 the largest exponent
+        let max = Float40 {
+            exponent: 0,
+            significand: 17179869183,
+        };
+        assert!(matches!(
+            max.checked_add(Float40::from_bigint(BigInt::from(1)).unwrap()),
+            Err(FloatsError::NumberTooBig(_))
+        ));
+        let huge = Float40 {
+            exponent: 31,
+            significand: 17179869183,
+        };
+        assert!(huge.checked_mul_int(10).is_err());
+        assert_eq!(
+            Float40 {
+                exponent: 31,
+                significand: 1,
+            }
+            .checked_mul_int(10)
+            .unwrap(),
+            Float40 {
+                exponent: 30,
+                significand: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn test_consts() {
+        assert_eq!(Float40::EXPONENT_BITS, 5);
+        assert_eq!(Float40::MAX_EXPONENT, 31);
+        assert_eq!(Float40::ENCODED_BYTES, 5);
+        assert_eq!(Float40::MAX_SIGNIFICAND, 17179869183);
+        assert_eq!(Float40::MIN_SIGNIFICAND, -17179869184);
+        assert_eq!(Floats::<u32, 16>::MAX_EXPONENT, 255);
+        assert_eq!(Floats::<u32, 16>::ENCODED_BYTES, 3);
+        assert_eq!(Floats::<u32, 16>::MAX_SIGNIFICAND, 65535);
+        assert_eq!(Floats::<u32, 16>::MIN_SIGNIFICAND, 0);
+        assert_eq!(Floats::<u128, 127>::MAX_SIGNIFICAND, i128::MAX);
+        assert_eq!(Floats::<i128, 127>::MIN_SIGNIFICAND, i128::MIN >> 1);
+
+        // usable for static buffers
+        let buf = [0u8; Float40::ENCODED_BYTES];
+        assert_eq!(buf.len(), Float40::zero().encode().len());
     }
 
     #[test]
@@ -589,6 +1184,22 @@ mod tests {
         assert_eq!(f.significand, 0);
         assert_eq!(f.to_bigint().to_u32().unwrap(), 0u32);
         assert_eq!(f.to_encoded_int().unwrap().to_u32().unwrap(), 0u32);
+
+        assert!(matches!(
+            Float40::from_encoded_bigint(BigInt::from(-65536)),
+            Err(FloatsError::NegativeEncoding(_))
+        ));
+        // exponent 31 is the largest in 5 bits, 32 needs a sixth
+        let f = Float40::from_encoded_bigint(BigInt::from(31u64 << 35)).unwrap();
+        assert_eq!((f.exponent, f.significand), (31, 0));
+        assert!(matches!(
+            Float40::from_encoded_bigint(BigInt::from(32u64 << 35)),
+            Err(FloatsError::ExponentTooBig)
+        ));
+        assert!(matches!(
+            Floats::<u32, 16>::from_encoded_bigint(BigInt::from(256u64 << 16)),
+            Err(FloatsError::ExponentTooBig)
+        ));
     }
 
     #[test]
@@ -599,10 +1210,147 @@ mod tests {
         let f = Float40::from_decimal(&d0, 18).unwrap();
         assert_eq!(f.exponent, 13);
         assert_eq!(f.significand, 123456);
-        let d = f.to_decimal(18);
+        let d = f.to_decimal(18).unwrap();
         assert_eq!(d, Decimal::from_str("1.23456").unwrap());
         let f2 = Float40::decode(&f.encode()).unwrap();
         assert_eq!(f2.exponent, 13);
         assert_eq!(f2.significand, 123456);
     }
+
+    #[test]
+    fn test_lossy() {
+        let l = Float40::from_bigint_lossy(BigInt::from(12345678901234567u64)).unwrap();
+        assert_eq!(l.value.exponent, 6);
+        assert_eq!(l.value.significand, 12345678901);
+        assert_eq!(l.abs_error, BigInt::from(234567));
+        assert!(l.rel_error > 1.8e-11 && l.rel_error < 2e-11);
+
+        // rounding up may carry into a shorter significand
+        let l = Float40::from_bigint_lossy(BigInt::from(999999999999u64)).unwrap();
+        assert_eq!((l.value.exponent, l.value.significand), (12, 1));
+        assert_eq!(l.abs_error, BigInt::from(-1));
+
+        let exact = Float40::from_bigint_lossy(BigInt::from(1_230_000)).unwrap();
+        assert_eq!(exact.value.to_bigint(), BigInt::from(1_230_000));
+        assert!(exact.abs_error.is_zero());
+        assert_eq!(exact.rel_error, 0.0);
+
+        let range = Float40::representable_range();
+        assert_eq!(
+            *range.end(),
+            BigInt::from((1i64 << 34) - 1) * BigInt::from(10).pow(31u32)
+        );
+        assert_eq!(
+            *range.start(),
+            -(BigInt::from(1i64 << 34) * BigInt::from(10).pow(31u32))
+        );
+        assert!(Float40::from_bigint_lossy(range.end().clone()).is_ok());
+        Float40::from_bigint_lossy(range.end() * 10).expect_err("expect too big error");
+
+        let d = Decimal::from_str("1.2345678901234567").unwrap();
+        let l = Float40::from_decimal_lossy(&d, 18).unwrap();
+        assert_eq!((l.value.exponent, l.value.significand), (8, 12345678901));
+        assert_eq!(
+            l.abs_error,
+            Decimal::from_str("0.0000000000234567").unwrap()
+        );
+        assert_eq!(l.value.to_decimal(18).unwrap() + l.abs_error, d);
+        assert!(l.rel_error > 1.8e-11 && l.rel_error < 2e-11);
+    }
+
+    #[test]
+    fn test_byte_aligned_layout() {
+        type F = Floats<i32, 20, { layout::BYTE_ALIGNED }>;
+        assert_eq!(F::EXPONENT_BITS, 8);
+        assert_eq!(F::SIGNIFICAND_BITS, 24);
+        assert_eq!(F::ENCODED_BYTES, 4);
+
+        let f = F {
+            exponent: 2,
+            significand: -1,
+        };
+        assert_eq!(f.encode(), vec![2, 0xff, 0xff, 0xff]);
+        assert_eq!(F::decode(&f.encode()).unwrap().significand, -1);
+        assert_eq!(F::from_bigint(BigInt::from(-100)).unwrap(), f);
+    }
+
+    #[test]
+    fn test_encoded_fr() {
+        let f = Float40 {
+            exponent: 3,
+            significand: -5,
+        };
+        let fr = f.to_encoded_fr().unwrap();
+        assert_eq!(
+            fr.to_bigint(),
+            (BigInt::from(3) << 35) + (BigInt::from(1) << 35) - 5
+        );
+        let f2 = Float40::from_encoded_fr(&fr).unwrap();
+        assert_eq!((f2.exponent, f2.significand), (3, -5));
+
+        let max = Fr::from_bigint((BigInt::from(1) << 40) - 1);
+        assert!(Float40::from_encoded_fr(&max).is_ok());
+        let too_big = Fr::from_bigint(BigInt::from(1) << 40);
+        assert!(matches!(
+            Float40::from_encoded_fr(&too_big),
+            Err(FloatsError::EncodingOverflow(_, 40))
+        ));
+        assert!(Float40::from_encoded_fr(&Fr::from_bigint(BigInt::from(1) << 200)).is_err());
+    }
+
+    #[test]
+    fn test_encoded_significand_bounds() {
+        for significand in [Float40::MIN_SIGNIFICAND, Float40::MAX_SIGNIFICAND] {
+            let f = Float40 {
+                exponent: 7,
+                significand: significand as i64,
+            };
+            let fr = f.to_encoded_fr().unwrap();
+            let decoded = Float40::from_encoded_fr(&fr).unwrap();
+            assert_eq!(
+                (decoded.exponent, decoded.significand),
+                (7, significand as i64)
+            );
+            assert_eq!(
+                Float40::decode(&f.encode()).unwrap().significand,
+                f.significand
+            );
+        }
+        // the encoding of MIN_SIGNIFICAND is 2^34, which must not read back as +2^34
+        assert_eq!(
+            Float40::from_encoded_bigint(BigInt::from(1i64 << 34))
+                .unwrap()
+                .significand,
+            -(1i64 << 34)
+        );
+
+        let over = Float40 {
+            exponent: 7,
+            significand: Float40::MAX_SIGNIFICAND as i64 + 1,
+        };
+        assert!(matches!(
+            over.to_encoded_int(),
+            Err(FloatsError::NumberTooBig(_))
+        ));
+        assert!(over.try_encode().is_err());
+        assert!(over.to_encoded_fr().is_err());
+        let under = Float40 {
+            exponent: 7,
+            significand: Float40::MIN_SIGNIFICAND as i64 - 1,
+        };
+        assert!(under.to_encoded_int().is_err());
+
+        // the byte aligned layout has spare bits above NBITS
+        type F = Floats<i32, 20, { layout::BYTE_ALIGNED }>;
+        assert!(F::decode(&[0, 0x08, 0, 0]).is_err());
+        assert!(F::decode(&[0, 0xf7, 0xff, 0xff]).is_err());
+        assert_eq!(
+            F::decode(&[0, 0xf8, 0, 0]).unwrap().significand,
+            F::MIN_SIGNIFICAND as i32
+        );
+        assert_eq!(
+            F::decode(&[0, 0x07, 0xff, 0xff]).unwrap().significand,
+            F::MAX_SIGNIFICAND as i32
+        );
+    }
 }
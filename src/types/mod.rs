@@ -2,6 +2,7 @@
 use std::str::FromStr;
 
 use ff::*;
+use num_bigint::Sign;
 
 pub use fnv::FnvHashMap as MerkleValueMapType;
 /// re-exports [`num_bigint::BigInt`]
@@ -9,15 +10,27 @@ pub use num_bigint::BigInt;
 /// re-exports [`rust_decimal::Decimal`]
 pub use rust_decimal::Decimal;
 
+mod bits;
+#[cfg(feature = "db")]
+mod db;
 mod decimal;
+#[cfg(feature = "l2-account")]
+mod eth;
+mod field_element;
 mod float864;
 mod floats;
+mod packer;
 mod pubkey;
 mod signature;
 
+pub use bits::*;
 pub use decimal::*;
+#[cfg(feature = "l2-account")]
+pub use eth::*;
+pub use field_element::*;
 pub use float864::*;
 pub use floats::*;
+pub use packer::*;
 pub use pubkey::*;
 pub use signature::*;
 
@@ -35,36 +48,85 @@ pub enum FrExtError {
     BufferError(#[from] std::io::Error),
     #[error(transparent)]
     PrimeFieldDecodingError(#[from] ff::PrimeFieldDecodingError),
+    #[error("value {0} overflows {1}")]
+    Overflow(BigInt, &'static str),
+    #[error("value {0} is not a canonical field element")]
+    NonCanonical(BigInt),
+    #[error(transparent)]
+    InvalidHex(#[from] hex::FromHexError),
+    #[error("invalid decimal string {0:?}")]
+    InvalidDecimal(String),
+    #[error("value {0} overflows {1} bits")]
+    BitsOverflow(BigInt, usize),
 }
 
 type Result<T, E = FrExtError> = std::result::Result<T, E>;
 
 pub trait FrExt: Sized {
     fn shl(&self, x: u32) -> Self;
+    /// Shift left by `x` bits, failing if the result does not fit in the field.
+    fn try_shl(&self, x: u32) -> Result<Self>;
     fn sub(&self, b: &Fr) -> Self;
     fn add(&self, b: &Fr) -> Self;
+    /// Plain Poseidon hash, panics on more inputs than the hasher width allows.
+    /// See [`crate::poseidon::hash`] for any number of inputs.
     fn hash(inputs: &[Self]) -> Self;
     fn from_u32(x: u32) -> Self;
     fn from_u64(x: u64) -> Self;
+    fn from_u128(x: u128) -> Self;
+    /// Negative values are mapped to their additive inverse in the field.
+    fn from_i64(x: i64) -> Self;
     fn from_bigint(x: BigInt) -> Self;
+    /// Convert a non-negative [`BigInt`] smaller than the field modulus.
+    fn try_from_bigint(x: BigInt) -> Result<Self>;
     fn from_str(x: &str) -> Self;
+    /// Parse a `0x` prefixed hex string or a decimal string.
+    fn try_from_str(x: &str) -> Result<Self>;
     fn from_slice(slice: &[u8]) -> Result<Self>;
+    /// Parse 32 big-endian bytes, failing if the value is not smaller than the modulus.
+    fn from_be_bytes(bytes: [u8; 32]) -> Result<Self>;
+    /// Parse 32 big-endian bytes, reducing the value modulo the field modulus.
+    fn from_be_bytes_reduced(bytes: [u8; 32]) -> Self;
+    fn to_be_bytes(&self) -> [u8; 32];
     fn to_hex_string(&self) -> String;
     fn to_hex_string_without_0x(&self) -> String;
     fn to_u32(&self) -> u32;
+    fn try_to_u32(&self) -> Result<u32>;
+    fn to_u64(&self) -> u64;
+    fn try_to_u64(&self) -> Result<u64>;
+    fn to_u128(&self) -> u128;
+    fn try_to_u128(&self) -> Result<u128>;
     fn to_i64(&self) -> i64;
+    fn try_to_i64(&self) -> Result<i64>;
+    fn to_i64_signed(&self) -> i64;
+    /// Inverse of [`FrExt::from_i64`], reading additive inverses of `1..=2^63` as negatives.
+    fn try_to_i64_signed(&self) -> Result<i64>;
     fn to_bigint(&self) -> BigInt;
     fn to_decimal_string(&self) -> String;
     fn to_decimal(&self, scale: u32) -> Decimal;
     fn to_vec_be(&self) -> Vec<u8>;
     fn to_bool(&self) -> Result<bool>;
+    /// Lowest `n` bits, least significant first. Fails if the value needs more than `n` bits.
+    fn to_bits_le(&self, n: usize) -> Result<Vec<bool>>;
+    /// Lowest `n` bits, most significant first. Fails if the value needs more than `n` bits.
+    fn to_bits_be(&self, n: usize) -> Result<Vec<bool>>;
+    fn from_bits_le(bits: &[bool]) -> Result<Self>;
+    fn from_bits_be(bits: &[bool]) -> Result<Self>;
 }
 
 impl FrExt for Fr {
     fn shl(&self, x: u32) -> Self {
+        self.try_shl(x).unwrap()
+    }
+
+    fn try_shl(&self, x: u32) -> Result<Self> {
         let mut repr = self.into_repr();
+        if repr.num_bits() as u64 + x as u64 > Fr::NUM_BITS as u64 {
+            return Err(FrExtError::Overflow(Self::to_bigint(self) << x, "Fr"));
+        }
         repr.shl(x);
-        Fr::from_repr(repr).unwrap()
+        // the shifted value may still be beyond the modulus with NUM_BITS bits
+        Fr::from_repr(repr).map_err(|_| FrExtError::Overflow(Self::to_bigint(self) << x, "Fr"))
     }
 
     fn sub(&self, b: &Fr) -> Self {
@@ -84,28 +146,53 @@ impl FrExt for Fr {
     }
 
     fn from_u32(x: u32) -> Self {
-        PrimeField::from_str(&format!("{}", x)).unwrap()
+        Self::from_u64(x as u64)
     }
 
     fn from_u64(x: u64) -> Self {
         Fr::from_repr(poseidon_rs::FrRepr::from(x)).unwrap()
     }
 
+    fn from_u128(x: u128) -> Self {
+        let mut repr = poseidon_rs::FrRepr::default();
+        repr.as_mut()[0] = x as u64;
+        repr.as_mut()[1] = (x >> 64) as u64;
+        Fr::from_repr(repr).unwrap()
+    }
+
+    fn from_i64(x: i64) -> Self {
+        let mut r = Self::from_u64(x.unsigned_abs());
+        if x < 0 {
+            r.negate();
+        }
+        r
+    }
+
     fn from_bigint(x: BigInt) -> Self {
-        let mut s = x.to_str_radix(16);
-        if s.len() % 2 != 0 {
-            // convert "f" to "0f"
-            s.insert(0, '0');
+        Self::try_from_bigint(x).unwrap()
+    }
+
+    fn try_from_bigint(x: BigInt) -> Result<Self> {
+        let (sign, digits) = x.to_u64_digits();
+        let mut repr = poseidon_rs::FrRepr::default();
+        if sign == Sign::Minus || digits.len() > repr.as_ref().len() {
+            return Err(FrExtError::NonCanonical(x));
         }
-        from_hex(&s).unwrap()
+        repr.as_mut()[..digits.len()].copy_from_slice(&digits);
+        Fr::from_repr(repr).map_err(|_| FrExtError::NonCanonical(x))
     }
 
     fn from_str(x: &str) -> Self {
+        Self::try_from_str(x).unwrap()
+    }
+
+    fn try_from_str(x: &str) -> Result<Self> {
         if x.starts_with("0x") {
-            Self::from_slice(&hex::decode(x.trim_start_matches("0x")).unwrap()).unwrap()
+            let bytes = hex::decode(x.trim_start_matches("0x"))?;
+            Self::try_from_bigint(BigInt::from_bytes_be(Sign::Plus, &bytes))
         } else {
-            let i = BigInt::from_str(x).unwrap();
-            Self::from_bigint(i)
+            let i = BigInt::from_str(x).map_err(|_| FrExtError::InvalidDecimal(x.to_string()))?;
+            Self::try_from_bigint(i)
         }
     }
 
@@ -126,6 +213,24 @@ impl FrExt for Fr {
         Ok(Fr::from_repr(repr)?)
     }
 
+    fn from_be_bytes(bytes: [u8; 32]) -> Result<Self> {
+        let mut repr = poseidon_rs::FrRepr::default();
+        repr.read_be(&bytes[..])?;
+        Ok(Fr::from_repr(repr)?)
+    }
+
+    fn from_be_bytes_reduced(bytes: [u8; 32]) -> Self {
+        let mut repr = poseidon_rs::FrRepr::default();
+        repr.read_be(&bytes[..]).unwrap();
+        reduce_repr(repr)
+    }
+
+    fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.into_repr().write_be(&mut bytes[..]).unwrap();
+        bytes
+    }
+
     fn to_hex_string(&self) -> String {
         "0x".to_string() + &to_hex(self)
     }
@@ -135,15 +240,83 @@ impl FrExt for Fr {
     }
 
     fn to_u32(&self) -> u32 {
-        Self::to_decimal_string(self).parse::<u32>().unwrap()
+        self.try_to_u32().unwrap()
+    }
+
+    fn try_to_u32(&self) -> Result<u32> {
+        let repr = self.into_repr();
+        let limbs = repr.as_ref();
+        if limbs[1..].iter().all(|l| *l == 0) && limbs[0] <= u32::MAX as u64 {
+            Ok(limbs[0] as u32)
+        } else {
+            Err(FrExtError::Overflow(Self::to_bigint(self), "u32"))
+        }
+    }
+
+    fn to_u64(&self) -> u64 {
+        self.try_to_u64().unwrap()
+    }
+
+    fn try_to_u64(&self) -> Result<u64> {
+        let repr = self.into_repr();
+        let limbs = repr.as_ref();
+        if limbs[1..].iter().all(|l| *l == 0) {
+            Ok(limbs[0])
+        } else {
+            Err(FrExtError::Overflow(Self::to_bigint(self), "u64"))
+        }
+    }
+
+    fn to_u128(&self) -> u128 {
+        self.try_to_u128().unwrap()
+    }
+
+    fn try_to_u128(&self) -> Result<u128> {
+        let repr = self.into_repr();
+        let limbs = repr.as_ref();
+        if limbs[2..].iter().all(|l| *l == 0) {
+            Ok(((limbs[1] as u128) << 64) + limbs[0] as u128)
+        } else {
+            Err(FrExtError::Overflow(Self::to_bigint(self), "u128"))
+        }
     }
 
     fn to_i64(&self) -> i64 {
-        Self::to_decimal_string(self).parse::<i64>().unwrap()
+        self.try_to_i64().unwrap()
+    }
+
+    fn try_to_i64(&self) -> Result<i64> {
+        match self.try_to_u64() {
+            Ok(x) if x <= i64::MAX as u64 => Ok(x as i64),
+            _ => Err(FrExtError::Overflow(Self::to_bigint(self), "i64")),
+        }
+    }
+
+    fn to_i64_signed(&self) -> i64 {
+        self.try_to_i64_signed().unwrap()
+    }
+
+    fn try_to_i64_signed(&self) -> Result<i64> {
+        if let Ok(x) = self.try_to_i64() {
+            return Ok(x);
+        }
+        let mut neg = *self;
+        neg.negate();
+        match neg.try_to_u64() {
+            // 2^63 wraps to i64::MIN
+            Ok(x) if x <= i64::MAX as u64 + 1 => Ok((x as i64).wrapping_neg()),
+            _ => Err(FrExtError::Overflow(Self::to_bigint(self), "i64")),
+        }
     }
 
     fn to_bigint(&self) -> BigInt {
-        BigInt::parse_bytes(to_hex(self).as_bytes(), 16).unwrap()
+        let digits: Vec<u32> = self
+            .into_repr()
+            .as_ref()
+            .iter()
+            .flat_map(|l| [*l as u32, (*l >> 32) as u32])
+            .collect();
+        BigInt::from_slice(Sign::Plus, &digits)
     }
 
     fn to_decimal_string(&self) -> String {
@@ -171,6 +344,51 @@ impl FrExt for Fr {
             Err(FrExtError::InvalidBool)
         }
     }
+
+    fn to_bits_le(&self, n: usize) -> Result<Vec<bool>> {
+        let repr = self.into_repr();
+        if repr.num_bits() as usize > n {
+            return Err(FrExtError::BitsOverflow(Self::to_bigint(self), n));
+        }
+        let limbs = repr.as_ref();
+        Ok((0..n)
+            .map(|i| i < limbs.len() * 64 && (limbs[i / 64] >> (i % 64)) & 1 == 1)
+            .collect())
+    }
+
+    fn to_bits_be(&self, n: usize) -> Result<Vec<bool>> {
+        let mut bits = self.to_bits_le(n)?;
+        bits.reverse();
+        Ok(bits)
+    }
+
+    fn from_bits_le(bits: &[bool]) -> Result<Self> {
+        let mut repr = poseidon_rs::FrRepr::default();
+        let limbs = repr.as_mut();
+        for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            if i >= limbs.len() * 64 {
+                return Err(FrExtError::InvalidLength);
+            }
+            limbs[i / 64] |= 1 << (i % 64);
+        }
+        Ok(Fr::from_repr(repr)?)
+    }
+
+    fn from_bits_be(bits: &[bool]) -> Result<Self> {
+        let mut bits = bits.to_vec();
+        bits.reverse();
+        Self::from_bits_le(&bits)
+    }
+}
+
+/// Reduce a full 256 bits repr modulo the field modulus.
+fn reduce_repr(mut repr: poseidon_rs::FrRepr) -> Fr {
+    let modulus = Fr::char();
+    // 2^256 / modulus < 6, so this loops at most 5 times
+    while repr >= modulus {
+        repr.sub_noborrow(&modulus);
+    }
+    Fr::from_repr(repr).unwrap()
 }
 
 #[cfg(test)]
@@ -198,3 +416,182 @@ fn test_fr() {
         out.to_hex_string_without_0x()
     );
 }
+
+#[cfg(test)]
+#[test]
+fn test_fr_try_conversions() {
+    assert_eq!(Fr::try_from_str("3141").unwrap(), Fr::from_u32(3141));
+    assert_eq!(Fr::try_from_str("0x0c45").unwrap(), Fr::from_u32(3141));
+    assert!(matches!(
+        Fr::try_from_str("0xzz"),
+        Err(FrExtError::InvalidHex(_))
+    ));
+    assert!(matches!(
+        Fr::try_from_str("31.41"),
+        Err(FrExtError::InvalidDecimal(_))
+    ));
+
+    // the field modulus itself is not a canonical element
+    let modulus = BigInt::from_str(
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+    )
+    .unwrap();
+    assert!(matches!(
+        Fr::try_from_bigint(modulus.clone()),
+        Err(FrExtError::NonCanonical(_))
+    ));
+    assert!(matches!(
+        Fr::try_from_bigint(BigInt::from(-1)),
+        Err(FrExtError::NonCanonical(_))
+    ));
+    let max = Fr::try_from_bigint(modulus.clone() - 1).unwrap();
+
+    assert_eq!(Fr::from_u64(u64::MAX).try_to_u64().unwrap(), u64::MAX);
+    assert!(matches!(
+        Fr::from_u64(u32::MAX as u64 + 1).try_to_u32(),
+        Err(FrExtError::Overflow(_, "u32"))
+    ));
+    assert!(matches!(
+        Fr::from_u64(u64::MAX).try_to_i64(),
+        Err(FrExtError::Overflow(_, "i64"))
+    ));
+    assert!(matches!(
+        max.try_to_u64(),
+        Err(FrExtError::Overflow(_, "u64"))
+    ));
+
+    assert_eq!(Fr::from_u32(1).try_shl(10).unwrap(), Fr::from_u32(1024));
+    assert!(Fr::from_u32(1).try_shl(253).is_ok());
+    assert!(matches!(
+        Fr::from_u32(1).try_shl(254),
+        Err(FrExtError::Overflow(_, "Fr"))
+    ));
+    assert!(matches!(max.try_shl(1), Err(FrExtError::Overflow(_, "Fr"))));
+    // fits in NUM_BITS bits but not below the modulus
+    let half = Fr::from_bigint((modulus.clone() + 1) / 2);
+    assert!(matches!(
+        half.try_shl(1),
+        Err(FrExtError::Overflow(_, "Fr"))
+    ));
+
+    assert!(matches!(
+        Fr::try_from_str("0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001"),
+        Err(FrExtError::NonCanonical(_))
+    ));
+    assert!(matches!(
+        Fr::try_from_str(&format!("0x{}", "ff".repeat(33))),
+        Err(FrExtError::NonCanonical(_))
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn test_fr_int_conversions() {
+    assert_eq!(Fr::from_u32(3141), PrimeField::from_str("3141").unwrap());
+    assert_eq!(Fr::from_u32(3141).to_u32(), 3141);
+    assert_eq!(Fr::from_u64(u64::MAX).to_u64(), u64::MAX);
+    assert_eq!(Fr::from_u128(u128::MAX).to_u128(), u128::MAX);
+    assert_eq!(
+        Fr::from_u128(u128::MAX).to_bigint(),
+        BigInt::from(u128::MAX)
+    );
+    assert_eq!(Fr::from_i64(i64::MAX).to_i64(), i64::MAX);
+    assert_eq!(Fr::from_i64(-1).add(&Fr::one()), Fr::zero());
+    assert_eq!(Fr::zero().to_i64(), 0);
+    assert!(matches!(
+        Fr::from_i64(-1).try_to_i64(),
+        Err(FrExtError::Overflow(_, "i64"))
+    ));
+    assert_eq!(Fr::from_i64(i64::MAX).to_i64_signed(), i64::MAX);
+    assert_eq!(Fr::from_i64(-1).to_i64_signed(), -1);
+    assert_eq!(Fr::from_i64(i64::MIN).to_i64_signed(), i64::MIN);
+    let mut below_min = Fr::from_i64(i64::MIN);
+    below_min.sub_assign(&Fr::one());
+    assert!(matches!(
+        below_min.try_to_i64_signed(),
+        Err(FrExtError::Overflow(_, "i64"))
+    ));
+    assert!(Fr::from_i64(-1).try_to_u128().is_err());
+
+    assert_eq!(Fr::zero().to_bigint(), BigInt::from(0));
+    let big = BigInt::from_str(
+        "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+    )
+    .unwrap();
+    let fr = Fr::from_bigint(big.clone());
+    assert_eq!(fr.to_bigint(), big);
+    assert_eq!(
+        fr.to_hex_string(),
+        "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000"
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_fr_to_decimal() {
+    assert_eq!(
+        Fr::from_u64(i64::MAX as u64).to_decimal(2),
+        Decimal::new(i64::MAX, 2)
+    );
+}
+
+#[cfg(test)]
+#[test]
+#[should_panic]
+fn test_fr_to_decimal_near_modulus() {
+    // p - 1 is not read as -1
+    Fr::from_i64(-1).to_decimal(2);
+}
+
+#[cfg(test)]
+#[test]
+fn test_fr_bits() {
+    let fr = Fr::from_u32(0b1011);
+    assert_eq!(
+        fr.to_bits_le(6).unwrap(),
+        [true, true, false, true, false, false]
+    );
+    assert_eq!(
+        fr.to_bits_be(6).unwrap(),
+        [false, false, true, false, true, true]
+    );
+    assert!(matches!(
+        fr.to_bits_le(3),
+        Err(FrExtError::BitsOverflow(_, 3))
+    ));
+    assert_eq!(Fr::from_bits_le(&fr.to_bits_le(6).unwrap()).unwrap(), fr);
+    assert_eq!(Fr::from_bits_be(&fr.to_bits_be(300).unwrap()).unwrap(), fr);
+
+    let max = Fr::from_i64(-1);
+    assert_eq!(
+        Fr::from_bits_le(&max.to_bits_le(254).unwrap()).unwrap(),
+        max
+    );
+    assert!(Fr::from_bits_le(&[true; 254]).is_err());
+    let mut bits = vec![false; 257];
+    bits[256] = true;
+    assert!(matches!(
+        Fr::from_bits_le(&bits),
+        Err(FrExtError::InvalidLength)
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn test_fr_be_bytes() {
+    let fr = Fr::from_u32(0x0c45);
+    let bytes = fr.to_be_bytes();
+    assert_eq!(&bytes[30..], &[0x0c, 0x45]);
+    assert_eq!(bytes.to_vec(), fr.to_vec_be());
+    assert_eq!(Fr::from_be_bytes(bytes).unwrap(), fr);
+    assert_eq!(Fr::from_be_bytes_reduced(bytes), fr);
+
+    // 2^256 - 1 = 5 * modulus + 0x0e0a77c19a07df2f666ea36f7879462e36fc76959f60cd29ac96341c4ffffffa
+    let max = [0xffu8; 32];
+    assert!(Fr::from_be_bytes(max).is_err());
+    assert_eq!(
+        Fr::from_be_bytes_reduced(max),
+        Fr::try_from_str("0x0e0a77c19a07df2f666ea36f7879462e36fc76959f60cd29ac96341c4ffffffa")
+            .unwrap()
+    );
+}
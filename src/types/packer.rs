@@ -0,0 +1,178 @@
+use super::{BigInt, Floats, FloatsError, Fr, FrExt, FrExtError, Significand};
+use crate::num_traits::{One, Signed, Zero};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackError {
+    #[error("value {0} does not fit in {1} bits")]
+    Overflow(BigInt, usize),
+    #[error("need {0} bits, only {1} left")]
+    Eof(usize, usize),
+    #[error("non-zero padding at bit {0}")]
+    Padding(usize),
+    #[error("bit width must not be zero")]
+    ZeroWidth,
+    #[error(transparent)]
+    Floats(#[from] FloatsError),
+    #[error(transparent)]
+    Fr(#[from] FrExtError),
+}
+
+type Result<T, E = PackError> = std::result::Result<T, E>;
+
+/// Pack several unsigned integers of fixed bit widths into one number.
+///
+/// Fields are laid out from the most significant bits to the least significant ones,
+/// in the order they are pushed, like the exponent and significand of [`Floats::encode`].
+///
+/// ```
+/// use fluidex_common::types::{BitPacker, Float40};
+///
+/// let amount = Float40 { exponent: 3, significand: 5 };
+/// let packed = BitPacker::new()
+///     .push(7u32, 32)?
+///     .push(2u16, 16)?
+///     .push_floats(amount)?
+///     .to_fr()?;
+/// # Ok::<(), fluidex_common::types::PackError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BitPacker {
+    value: BigInt,
+    bits: usize,
+}
+
+impl BitPacker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pack a list of `(value, bit width)` pairs.
+    pub fn from_fields<I, T>(fields: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (T, usize)>,
+        T: Into<BigInt>,
+    {
+        fields
+            .into_iter()
+            .try_fold(Self::new(), |packer, (value, width)| {
+                packer.push(value, width)
+            })
+    }
+
+    /// Append `value` as a `width` bits field, failing if it is negative or needs more bits.
+    pub fn push<T: Into<BigInt>>(mut self, value: T, width: usize) -> Result<Self> {
+        let value = value.into();
+        if value.is_negative() || value.bits() > width as u64 {
+            return Err(PackError::Overflow(value, width));
+        }
+        self.value = (self.value << width) | value;
+        self.bits += width;
+        Ok(self)
+    }
+
+    /// Append the encoded integer of `f`, taking [`Floats::ENCODED_BYTES`] bytes.
+    pub fn push_floats<T: Significand, const NBITS: usize, const LAYOUT: u8>(
+        self,
+        f: Floats<T, NBITS, LAYOUT>,
+    ) -> Result<Self> {
+        let encoded = f.to_encoded_int()?;
+        self.push(encoded, Floats::<T, NBITS, LAYOUT>::ENCODED_BYTES * 8)
+    }
+
+    /// Total bit width of the pushed fields.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    pub fn to_bigint(&self) -> BigInt {
+        self.value.clone()
+    }
+
+    pub fn to_fr(&self) -> Result<Fr> {
+        Ok(Fr::try_from_bigint(self.value.clone())?)
+    }
+
+    /// Big-endian bytes, zero padded at the front to a whole number of bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = (self.bits + 7) / 8;
+        if self.value.is_zero() {
+            return vec![0u8; len];
+        }
+        let (_, bytes) = self.value.to_bytes_be();
+        let mut ret = vec![0u8; len - bytes.len()];
+        ret.extend_from_slice(&bytes);
+        ret
+    }
+
+    /// Split a packed number back into fields of the given widths.
+    pub fn unpack(value: &BigInt, widths: &[usize]) -> Result<Vec<BigInt>> {
+        let total: usize = widths.iter().sum();
+        if value.is_negative() || value.bits() > total as u64 {
+            return Err(PackError::Overflow(value.clone(), total));
+        }
+        let mut shift = total;
+        Ok(widths
+            .iter()
+            .map(|width| {
+                shift -= width;
+                let mask = (BigInt::one() << width) - 1;
+                (value >> shift) & mask
+            })
+            .collect())
+    }
+
+    pub fn unpack_fr(fr: &Fr, widths: &[usize]) -> Result<Vec<BigInt>> {
+        Self::unpack(&fr.to_bigint(), widths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Float40;
+
+    #[test]
+    fn test_pack() {
+        let packer = BitPacker::new()
+            .push(1u32, 4)
+            .unwrap()
+            .push(0u32, 4)
+            .unwrap()
+            .push(0xabu32, 8)
+            .unwrap();
+        assert_eq!(packer.bits(), 16);
+        assert_eq!(packer.to_bytes(), vec![0x10, 0xab]);
+        assert_eq!(packer.to_fr().unwrap(), Fr::from_u32(0x10ab));
+        assert_eq!(
+            BitPacker::unpack_fr(&Fr::from_u32(0x10ab), &[4, 4, 8]).unwrap(),
+            vec![BigInt::from(1), BigInt::from(0), BigInt::from(0xab)]
+        );
+
+        let zeros = BitPacker::from_fields(vec![(0u32, 3), (0, 6)]).unwrap();
+        assert_eq!(zeros.to_bytes(), vec![0, 0]);
+
+        assert!(matches!(
+            BitPacker::new().push(16u32, 4),
+            Err(PackError::Overflow(_, 4))
+        ));
+        assert!(BitPacker::new().push(-1i32, 4).is_err());
+        assert!(BitPacker::unpack(&BigInt::from(256), &[4, 4]).is_err());
+    }
+
+    #[test]
+    fn test_pack_floats() {
+        let amount = Float40 {
+            exponent: 1,
+            significand: 16777216,
+        };
+        let packed = BitPacker::new()
+            .push(3u32, 32)
+            .unwrap()
+            .push_floats(amount)
+            .unwrap();
+        assert_eq!(packed.bits(), 72);
+        let bytes = packed.to_bytes();
+        assert_eq!(&bytes[..4], &[0, 0, 0, 3]);
+        assert_eq!(&bytes[4..], &amount.encode()[..]);
+    }
+}
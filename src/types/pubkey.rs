@@ -1,7 +1,20 @@
 use std::convert::TryInto;
 
+use once_cell::sync::Lazy;
+
+use super::{BigInt, Fr, FrExt};
 use crate::babyjubjub_rs::decompress_point;
 pub use crate::babyjubjub_rs::Point as Pubkey;
+use crate::ff::Field;
+
+/// Order of the prime order subgroup of babyjubjub
+pub(crate) static SUBORDER: Lazy<BigInt> = Lazy::new(|| {
+    BigInt::parse_bytes(
+        b"2736030358979909402780800718157159386076813972158567259200215660948447373041",
+        10,
+    )
+    .unwrap()
+});
 
 #[derive(Debug, thiserror::Error)]
 pub enum PubkeyExtError {
@@ -11,6 +24,12 @@ pub enum PubkeyExtError {
     InvalidLength(Vec<u8>),
     #[error("{0}")]
     InvalidPoint(String),
+    #[error("invalid sign {0}, expect 0 or 1")]
+    InvalidSign(Fr),
+    #[error("point is not on the curve")]
+    NotOnCurve,
+    #[error("point is not in the prime order subgroup")]
+    NotInSubgroup,
 }
 
 type Result<T, E = PubkeyExtError> = std::result::Result<T, E>;
@@ -19,6 +38,16 @@ type Result<T, E = PubkeyExtError> = std::result::Result<T, E>;
 pub trait PubkeyExt: Sized {
     /// Parse a packed pubkey hex string
     fn from_str(pubkey: &str) -> Result<Self>;
+    /// Packed pubkey hex string with `0x` prefix
+    fn to_hex_string(&self) -> String;
+    /// Build from the `(sign, ay)` pair stored in the account tree, `sign` being 1 if
+    /// `ax` is in the upper half of the field
+    fn from_sign_ay(sign: &Fr, ay: &Fr) -> Result<Self>;
+    /// Build from the affine coordinates, checking the point is on the curve and in the
+    /// prime order subgroup
+    fn from_ax_ay(ax: &Fr, ay: &Fr) -> Result<Self>;
+    /// `(ax, ay, sign)` as used by the circuits
+    fn to_circuit_components(&self) -> (Fr, Fr, Fr);
 }
 
 impl PubkeyExt for Pubkey {
@@ -29,4 +58,113 @@ impl PubkeyExt for Pubkey {
         let pubkey_packed = hex::decode(pubkey)?;
         decompress_point(pubkey_packed.try_into().map_err(InvalidLength)?).map_err(InvalidPoint)
     }
+
+    fn to_hex_string(&self) -> String {
+        "0x".to_string() + &hex::encode(self.compress())
+    }
+
+    fn from_sign_ay(sign: &Fr, ay: &Fr) -> Result<Self> {
+        let negative = sign
+            .to_bool()
+            .map_err(|_| PubkeyExtError::InvalidSign(*sign))?;
+        // packed form is ay in little-endian with the sign in the highest bit
+        let mut packed = ay.to_be_bytes();
+        packed.reverse();
+        if negative {
+            packed[31] |= 0x80;
+        }
+        let point = decompress_point(packed).map_err(PubkeyExtError::InvalidPoint)?;
+        check_subgroup(point)
+    }
+
+    fn from_ax_ay(ax: &Fr, ay: &Fr) -> Result<Self> {
+        if !is_on_curve(ax, ay) {
+            return Err(PubkeyExtError::NotOnCurve);
+        }
+        check_subgroup(Pubkey { x: *ax, y: *ay })
+    }
+
+    fn to_circuit_components(&self) -> (Fr, Fr, Fr) {
+        let sign = if self.compress()[31] & 0x80 != 0x00 {
+            Fr::one()
+        } else {
+            Fr::zero()
+        };
+        (self.x, self.y, sign)
+    }
+}
+
+/// a * x^2 + y^2 = 1 + d * x^2 * y^2
+fn is_on_curve(x: &Fr, y: &Fr) -> bool {
+    let mut x2 = *x;
+    x2.square();
+    let mut y2 = *y;
+    y2.square();
+
+    let mut lhs = Fr::from_u32(168700);
+    lhs.mul_assign(&x2);
+    lhs.add_assign(&y2);
+
+    let mut rhs = Fr::from_u32(168696);
+    rhs.mul_assign(&x2);
+    rhs.mul_assign(&y2);
+    rhs.add_assign(&Fr::one());
+
+    lhs == rhs
+}
+
+pub(crate) fn check_subgroup(point: Pubkey) -> Result<Pubkey> {
+    let identity = point.mul_scalar(&SUBORDER);
+    if identity.x.is_zero() && identity.y == Fr::one() {
+        Ok(point)
+    } else {
+        Err(PubkeyExtError::NotInSubgroup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::babyjubjub_rs::PrivateKey;
+
+    #[test]
+    fn test_circuit_components() {
+        for seed in 1..4u8 {
+            let pubkey = PrivateKey::import(vec![seed; 32]).unwrap().public();
+            let (ax, ay, sign) = pubkey.to_circuit_components();
+            assert_eq!((ax, ay), (pubkey.x, pubkey.y));
+
+            let from_sign_ay = Pubkey::from_sign_ay(&sign, &ay).unwrap();
+            assert_eq!((from_sign_ay.x, from_sign_ay.y), (ax, ay));
+            let from_ax_ay = Pubkey::from_ax_ay(&ax, &ay).unwrap();
+            assert_eq!(from_ax_ay.compress(), pubkey.compress());
+
+            let mut flipped = Fr::one();
+            flipped.sub_assign(&sign);
+            let other = Pubkey::from_sign_ay(&flipped, &ay).unwrap();
+            let mut neg_x = ax;
+            neg_x.negate();
+            assert_eq!(other.x, neg_x);
+
+            let mut off = ax;
+            off.add_assign(&Fr::one());
+            assert!(matches!(
+                Pubkey::from_ax_ay(&off, &ay),
+                Err(PubkeyExtError::NotOnCurve)
+            ));
+        }
+
+        assert!(matches!(
+            Pubkey::from_sign_ay(&Fr::from_u32(2), &Fr::one()),
+            Err(PubkeyExtError::InvalidSign(_))
+        ));
+
+        // (0, -1) is on the curve but of order 2
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        assert!(matches!(
+            Pubkey::from_ax_ay(&Fr::zero(), &minus_one),
+            Err(PubkeyExtError::NotInSubgroup)
+        ));
+    }
 }
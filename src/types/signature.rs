@@ -19,6 +19,8 @@ type Result<T, E = SignatureExtError> = std::result::Result<T, E>;
 pub trait SignatureExt: Sized {
     /// Parse a packed signature hex string
     fn from_str(pubkey: &str) -> Result<Self>;
+    /// Packed signature hex string with `0x` prefix
+    fn to_hex_string(&self) -> String;
 }
 
 impl SignatureExt for Signature {
@@ -30,4 +32,8 @@ impl SignatureExt for Signature {
         decompress_signature(&sig_packed_vec.try_into().map_err(InvalidLength)?)
             .map_err(InvalidPoint)
     }
+
+    fn to_hex_string(&self) -> String {
+        "0x".to_string() + &hex::encode(self.compress())
+    }
 }